@@ -0,0 +1,66 @@
+// Copyright (c) Viable Systems and TezEdge Contributors
+// SPDX-License-Identifier: MIT
+
+//! Measures the construction of small OCaml blocks: 12-tuples, whose fields go through
+//! the write barrier, and the `Some`/`Ok`/list constructors, which fill their fresh blocks
+//! directly.
+//!
+//! Run with `cargo bench --bench block_construction` (requires an OCaml toolchain).
+
+use ocaml_interop::{BoxRoot, OCamlInt, OCamlRuntime, ToOCaml};
+use std::time::{Duration, Instant};
+
+const ITERATIONS: u32 = 1_000_000;
+
+type Tuple12 = (i64, i64, i64, i64, i64, i64, i64, i64, i64, i64, i64, i64);
+type OCamlTuple12 = (
+    OCamlInt,
+    OCamlInt,
+    OCamlInt,
+    OCamlInt,
+    OCamlInt,
+    OCamlInt,
+    OCamlInt,
+    OCamlInt,
+    OCamlInt,
+    OCamlInt,
+    OCamlInt,
+    OCamlInt,
+);
+
+fn measure(cr: &mut OCamlRuntime, name: &str, mut construct: impl FnMut(&mut OCamlRuntime)) {
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        construct(cr);
+    }
+    let total: Duration = start.elapsed();
+    println!(
+        "{} ({} iterations): {:?}/iter",
+        name,
+        ITERATIONS,
+        total / ITERATIONS
+    );
+}
+
+fn main() {
+    let mut cr = OCamlRuntime::init();
+
+    let tuple: Tuple12 = (0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11);
+    measure(&mut cr, "12-tuple", |cr| {
+        let root: BoxRoot<OCamlTuple12> = tuple.to_boxroot(cr);
+        drop(root);
+    });
+    measure(&mut cr, "Some", |cr| {
+        let root: BoxRoot<Option<OCamlInt>> = Some(1i64).to_boxroot(cr);
+        drop(root);
+    });
+    measure(&mut cr, "Ok", |cr| {
+        let root: BoxRoot<Result<OCamlInt, String>> = Ok::<i64, String>(1).to_boxroot(cr);
+        drop(root);
+    });
+    let list = [1i64, 2, 3, 4];
+    measure(&mut cr, "4-element list", |cr| {
+        let root: BoxRoot<ocaml_interop::OCamlList<OCamlInt>> = list.to_boxroot(cr);
+        drop(root);
+    });
+}
@@ -0,0 +1,38 @@
+// Copyright (c) Viable Systems and TezEdge Contributors
+// SPDX-License-Identifier: MIT
+
+//! Compares rooting converted values with fresh roots and with pooled roots.
+//!
+//! Run with `cargo bench --bench boxroot_pool` (requires an OCaml toolchain).
+
+use ocaml_interop::{BoxRoot, BoxRootPool, OCamlInt64, OCamlRuntime, ToOCaml};
+use std::time::Instant;
+
+const CONVERSIONS: i64 = 1_000_000;
+
+fn main() {
+    let mut cr = OCamlRuntime::init();
+
+    let start = Instant::now();
+    for n in 0..CONVERSIONS {
+        let root: BoxRoot<OCamlInt64> = n.to_boxroot(&mut cr);
+        drop(root);
+    }
+    println!(
+        "Fresh roots ({} conversions): {:?}",
+        CONVERSIONS,
+        start.elapsed()
+    );
+
+    let pool = BoxRootPool::new(&cr, 16);
+    let start = Instant::now();
+    for n in 0..CONVERSIONS {
+        let root = pool.root(ToOCaml::<OCamlInt64>::to_ocaml(&n, &mut cr));
+        drop(root);
+    }
+    println!(
+        "Pooled roots ({} conversions): {:?}",
+        CONVERSIONS,
+        start.elapsed()
+    );
+}
@@ -0,0 +1,55 @@
+// Copyright (c) Viable Systems and TezEdge Contributors
+// SPDX-License-Identifier: MIT
+
+//! Measures the conversion of large `Vec<i64>` values into OCaml lists.
+//!
+//! Run with `cargo bench --bench list_conversion` (requires an OCaml toolchain).
+
+use ocaml_interop::{BoxRoot, ListBuilder, OCamlInt, OCamlList, OCamlRuntime, ToOCaml};
+use std::time::{Duration, Instant};
+
+const LIST_LEN: i64 = 1_000_000;
+const BUILDER_LIST_LEN: i64 = 100_000;
+const ITERATIONS: u32 = 20;
+
+fn measure(
+    cr: &mut OCamlRuntime,
+    name: &str,
+    len: i64,
+    convert: impl Fn(&mut OCamlRuntime, &[i64]) -> BoxRoot<OCamlList<OCamlInt>>,
+) {
+    let data: Vec<i64> = (0..len).collect();
+
+    let mut total = Duration::default();
+    for _ in 0..ITERATIONS {
+        let start = Instant::now();
+        let list = convert(cr, &data);
+        total += start.elapsed();
+        drop(list);
+    }
+
+    println!(
+        "{} ({} elements) to OCamlList<OCamlInt>: {:?}/iter",
+        name,
+        len,
+        total / ITERATIONS
+    );
+}
+
+fn main() {
+    let mut cr = OCamlRuntime::init();
+
+    measure(&mut cr, "Vec<i64>", LIST_LEN, |cr, data| {
+        data.to_boxroot(cr)
+    });
+    measure(&mut cr, "Vec<i64>", BUILDER_LIST_LEN, |cr, data| {
+        data.to_boxroot(cr)
+    });
+    measure(&mut cr, "ListBuilder", BUILDER_LIST_LEN, |cr, data| {
+        let mut builder = ListBuilder::new();
+        for n in data {
+            builder.push(cr, n);
+        }
+        builder.finish()
+    });
+}
@@ -0,0 +1,497 @@
+// Copyright (c) Viable Systems and TezEdge Contributors
+// SPDX-License-Identifier: MIT
+
+//! Derive macros for [ocaml-interop](https://docs.rs/ocaml-interop).
+//!
+//! This crate is not meant to be used directly, the macros it provides are
+//! re-exported by `ocaml-interop`.
+
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::{format_ident, quote};
+use syn::{
+    parse_macro_input, spanned::Spanned, Attribute, Data, DataEnum, DeriveInput, Fields,
+    FieldsNamed, Type,
+};
+
+// OCaml block tags from `No_scan_tag` (251) are reserved, and some of the tags right
+// below it have a special meaning too, non-constant constructors use tags 0 to 245.
+const MAX_BLOCK_TAG: u8 = 245;
+
+/// Derives `ToOCaml` for a struct or an enum.
+///
+/// Structs with named fields are converted into OCaml records, each field being stored
+/// in the OCaml record in declaration order. What is important is the order of the
+/// fields, not their names. Like in OCaml, records whose fields are all converted into
+/// `OCamlFloat` are stored flat, as a block of unboxed doubles.
+///
+/// Enums are converted into OCaml variants. As in OCaml, constructors without arguments
+/// and constructors with arguments are numbered separately, in declaration order. Unit
+/// variants are converted into immediate values, and variants with fields into blocks
+/// whose tag is the constructor number. The number of a constructor can be set with
+/// the `#[ocaml(tag = N)]` attribute on the variant, the variants of the same kind that
+/// follow it are numbered from there.
+///
+/// By default the type is converted into `OCaml<Self>`, and each field is converted
+/// into an OCaml value of the same type as the field. Both can be overridden with the
+/// `#[ocaml(as_ = "OCamlType")]` attribute, on the type and on individual fields.
+///
+/// Enums marked with `#[ocaml(polymorphic_variant)]` are converted into OCaml polymorphic
+/// variants instead, whose tags are the hashes of the variant names, as computed by
+/// OCaml's `caml_hash_variant`. Variants with more than one field are converted into
+/// constructors that take a tuple.
+///
+/// Structs with a single field can be marked with `#[ocaml(transparent)]`, they are then
+/// converted exactly as their field would be, into the OCaml type of the field, instead
+/// of being wrapped in a block. This is meant for newtypes.
+///
+/// ```ignore
+/// // Assuming OCaml type declarations like:
+/// //
+/// //      type point = { x: int; y: int }
+/// //
+/// //      type shape =
+/// //        | Circle of float
+/// //        | Rect of float * float
+/// //        | Unit
+///
+/// #[derive(ToOCaml)]
+/// struct Point {
+///     #[ocaml(as_ = "OCamlInt")]
+///     x: i64,
+///     #[ocaml(as_ = "OCamlInt")]
+///     y: i64,
+/// }
+///
+/// #[derive(ToOCaml)]
+/// enum Shape {
+///     Circle(#[ocaml(as_ = "OCamlFloat")] f64),
+///     Rect(
+///         #[ocaml(as_ = "OCamlFloat")] f64,
+///         #[ocaml(as_ = "OCamlFloat")] f64,
+///     ),
+///     Unit,
+/// }
+///
+/// // Converted into a bare OCaml `int`
+/// #[derive(ToOCaml)]
+/// #[ocaml(transparent)]
+/// struct UserId(#[ocaml(as_ = "OCamlInt")] i64);
+///
+/// // Converted into `` `Circle 1.0 ``, `` `Rect (2.0, 3.0) `` and `` `Unit ``
+/// #[derive(ToOCaml)]
+/// #[ocaml(polymorphic_variant)]
+/// enum PolyShape {
+///     Circle(#[ocaml(as_ = "OCamlFloat")] f64),
+///     Rect(
+///         #[ocaml(as_ = "OCamlFloat")] f64,
+///         #[ocaml(as_ = "OCamlFloat")] f64,
+///     ),
+///     Unit,
+/// }
+/// ```
+#[proc_macro_derive(ToOCaml, attributes(ocaml))]
+pub fn derive_to_ocaml(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_to_ocaml(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+fn expand_to_ocaml(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let name = &input.ident;
+    let attrs = OCamlAttrs::parse(&input.attrs)?;
+    if let Some(tag) = attrs.tag {
+        return Err(syn::Error::new(
+            tag.span(),
+            "`tag` is only allowed on enum variants",
+        ));
+    }
+    if let Some(polymorphic_variant) = attrs.polymorphic_variant {
+        if !matches!(input.data, Data::Enum(_)) {
+            return Err(syn::Error::new(
+                polymorphic_variant,
+                "`polymorphic_variant` is only allowed on enums",
+            ));
+        }
+    }
+    if let Some(transparent) = attrs.transparent {
+        if let Some(typ) = attrs.as_ {
+            return Err(syn::Error::new_spanned(
+                typ,
+                "`as_` can't be combined with `transparent`, set it on the field instead",
+            ));
+        }
+        return expand_transparent(&input, transparent);
+    }
+    let ocaml_typ = match attrs.as_ {
+        Some(typ) => typ,
+        None => syn::parse_quote!(Self),
+    };
+
+    let body = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) if !fields.named.is_empty() => expand_record(&ocaml_typ, fields)?,
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    name,
+                    "ToOCaml can only be derived for structs with named fields, \
+                     or for structs with a single field marked `#[ocaml(transparent)]`",
+                ))
+            }
+        },
+        Data::Enum(data) if attrs.polymorphic_variant.is_some() => expand_poly_variant(data)?,
+        Data::Enum(data) => expand_variant(&ocaml_typ, data)?,
+        Data::Union(_) => {
+            return Err(syn::Error::new_spanned(
+                name,
+                "ToOCaml can't be derived for unions",
+            ))
+        }
+    };
+
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    Ok(quote! {
+        unsafe impl #impl_generics ::ocaml_interop::ToOCaml<#ocaml_typ>
+            for #name #ty_generics #where_clause
+        {
+            fn to_ocaml<'a>(
+                &self,
+                cr: &'a mut ::ocaml_interop::OCamlRuntime,
+            ) -> ::ocaml_interop::OCaml<'a, #ocaml_typ> {
+                #body
+            }
+        }
+    })
+}
+
+fn expand_transparent(input: &DeriveInput, transparent: Span) -> syn::Result<TokenStream2> {
+    let name = &input.ident;
+    let field = match &input.data {
+        Data::Struct(data) if data.fields.len() == 1 => data.fields.iter().next().unwrap(),
+        _ => {
+            return Err(syn::Error::new(
+                transparent,
+                "`transparent` is only allowed on structs with a single field",
+            ))
+        }
+    };
+    let field_access = match &field.ident {
+        Some(field_name) => quote!(self.#field_name),
+        None => quote!(self.0),
+    };
+    let ocaml_typ = field_ocaml_type(field)?;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    Ok(quote! {
+        unsafe impl #impl_generics ::ocaml_interop::ToOCaml<#ocaml_typ>
+            for #name #ty_generics #where_clause
+        {
+            fn to_ocaml<'a>(
+                &self,
+                cr: &'a mut ::ocaml_interop::OCamlRuntime,
+            ) -> ::ocaml_interop::OCaml<'a, #ocaml_typ> {
+                ::ocaml_interop::ToOCaml::<#ocaml_typ>::to_ocaml(&#field_access, cr)
+            }
+        }
+    })
+}
+
+fn expand_record(ocaml_typ: &Type, fields: &FieldsNamed) -> syn::Result<TokenStream2> {
+    let mut values = Vec::with_capacity(fields.named.len());
+    for field in fields.named.iter() {
+        let field_name = &field.ident;
+        values.push((quote!(&self.#field_name), field_ocaml_type(field)?));
+    }
+    let field_count = values.len();
+
+    // OCaml stores records whose fields are all floats flat, as unboxed doubles
+    if field_count > 0 && values.iter().all(|(_, typ)| is_ocaml_float(typ)) {
+        let floats = values.iter().map(|(value, _)| {
+            quote! {
+                ::ocaml_interop::ToOCaml::<::ocaml_interop::OCamlFloat>::to_ocaml(#value, cr)
+                    .to_rust::<f64>()
+            }
+        });
+        return Ok(quote! {
+            let fields: [f64; #field_count] = [#(#floats),*];
+            unsafe { ::ocaml_interop::alloc_float_record(cr, &fields) }
+        });
+    }
+
+    let stores = expand_field_stores(&values);
+
+    Ok(quote! {
+        unsafe {
+            let block: ::ocaml_interop::BoxRoot<#ocaml_typ> = ::ocaml_interop::BoxRoot::new(
+                ::ocaml_interop::internal::alloc_tuple(cr, #field_count),
+            );
+            #stores
+            cr.get(&block)
+        }
+    })
+}
+
+fn is_ocaml_float(typ: &Type) -> bool {
+    match typ {
+        Type::Path(path) => matches!(
+            path.path.segments.last(),
+            Some(segment) if segment.ident == "OCamlFloat" && segment.arguments.is_empty()
+        ),
+        _ => false,
+    }
+}
+
+fn expand_variant(ocaml_typ: &Type, data: &DataEnum) -> syn::Result<TokenStream2> {
+    let mut unit_tag: i64 = 0;
+    let mut block_tag: i64 = 0;
+    let mut arms = Vec::with_capacity(data.variants.len());
+
+    for variant in data.variants.iter() {
+        let variant_name = &variant.ident;
+        let attrs = variant_attrs(variant)?;
+
+        if variant.fields.is_empty() {
+            if let Some(tag) = &attrs.tag {
+                unit_tag = tag.base10_parse()?;
+                if unit_tag < 0 {
+                    return Err(syn::Error::new(
+                        tag.span(),
+                        "the tag of a constant constructor can't be negative",
+                    ));
+                }
+            }
+            let pattern = match &variant.fields {
+                Fields::Named(_) => quote!(Self::#variant_name {}),
+                Fields::Unnamed(_) => quote!(Self::#variant_name()),
+                Fields::Unit => quote!(Self::#variant_name),
+            };
+            arms.push(quote! {
+                #pattern => unsafe {
+                    ::ocaml_interop::OCaml::new(
+                        cr,
+                        ::ocaml_interop::OCaml::of_i64_unchecked(#unit_tag).raw(),
+                    )
+                },
+            });
+            unit_tag += 1;
+            continue;
+        }
+
+        if let Some(tag) = &attrs.tag {
+            block_tag = tag.base10_parse()?;
+            if !(0..=i64::from(MAX_BLOCK_TAG)).contains(&block_tag) {
+                return Err(syn::Error::new(
+                    tag.span(),
+                    format!(
+                        "the tag of a non-constant constructor must be between 0 and {}",
+                        MAX_BLOCK_TAG
+                    ),
+                ));
+            }
+        }
+        if block_tag > i64::from(MAX_BLOCK_TAG) {
+            return Err(syn::Error::new_spanned(
+                variant_name,
+                format!(
+                    "too many non-constant constructors, the tag of this variant would be {} \
+                     but the maximum is {}",
+                    block_tag, MAX_BLOCK_TAG
+                ),
+            ));
+        }
+
+        let (pattern, values) = variant_fields(variant)?;
+        let field_count = values.len();
+        let tag = block_tag as u8;
+        let stores = expand_field_stores(&values);
+        arms.push(quote! {
+            #pattern => unsafe {
+                let block: ::ocaml_interop::BoxRoot<#ocaml_typ> = ::ocaml_interop::BoxRoot::new(
+                    ::ocaml_interop::internal::alloc_variant(cr, #tag, #field_count),
+                );
+                #stores
+                cr.get(&block)
+            },
+        });
+        block_tag += 1;
+    }
+
+    Ok(quote! {
+        match self {
+            #(#arms)*
+        }
+    })
+}
+
+fn expand_poly_variant(data: &DataEnum) -> syn::Result<TokenStream2> {
+    let mut arms = Vec::with_capacity(data.variants.len());
+
+    for variant in data.variants.iter() {
+        let variant_name = &variant.ident;
+        let attrs = variant_attrs(variant)?;
+        if let Some(tag) = attrs.tag {
+            return Err(syn::Error::new(
+                tag.span(),
+                "`tag` is not allowed in polymorphic variants, \
+                 the tag is the hash of the variant name",
+            ));
+        }
+        let hash = quote!(::ocaml_interop::polymorphic_variant_tag_hash!(#variant_name));
+
+        let (pattern, values) = variant_fields(variant)?;
+        let arm = match values.as_slice() {
+            [] => quote! {
+                #pattern => unsafe { ::ocaml_interop::OCaml::new(cr, #hash) },
+            },
+            [(value, field_typ)] => quote! {
+                #pattern => unsafe {
+                    let payload: ::ocaml_interop::BoxRoot<#field_typ> =
+                        ::ocaml_interop::ToOCaml::<#field_typ>::to_boxroot(#value, cr);
+                    let raw = ::ocaml_interop::alloc_poly_variant(cr, #hash, &payload).raw();
+                    ::ocaml_interop::OCaml::new(cr, raw)
+                },
+            },
+            _ => {
+                let field_count = values.len();
+                let field_types = values.iter().map(|(_, field_typ)| field_typ);
+                let stores = expand_field_stores(&values);
+                quote! {
+                    #pattern => unsafe {
+                        let block: ::ocaml_interop::BoxRoot<(#(#field_types),*)> =
+                            ::ocaml_interop::BoxRoot::new(
+                                ::ocaml_interop::internal::alloc_tuple(cr, #field_count),
+                            );
+                        #stores
+                        let raw = ::ocaml_interop::alloc_poly_variant(cr, #hash, &block).raw();
+                        ::ocaml_interop::OCaml::new(cr, raw)
+                    },
+                }
+            }
+        };
+        arms.push(arm);
+    }
+
+    Ok(quote! {
+        match self {
+            #(#arms)*
+        }
+    })
+}
+
+fn variant_attrs(variant: &syn::Variant) -> syn::Result<OCamlAttrs> {
+    let attrs = OCamlAttrs::parse(&variant.attrs)?;
+    if let Some(transparent) = attrs.transparent {
+        return Err(syn::Error::new(
+            transparent,
+            "`transparent` is only allowed on structs",
+        ));
+    }
+    if let Some(polymorphic_variant) = attrs.polymorphic_variant {
+        return Err(syn::Error::new(
+            polymorphic_variant,
+            "`polymorphic_variant` is only allowed on enums",
+        ));
+    }
+    if let Some(typ) = attrs.as_ {
+        return Err(syn::Error::new_spanned(
+            typ,
+            "`as_` is not allowed on enum variants",
+        ));
+    }
+    Ok(attrs)
+}
+
+// Pattern binding the fields of a variant, and the values to convert for each of them.
+fn variant_fields(
+    variant: &syn::Variant,
+) -> syn::Result<(TokenStream2, Vec<(TokenStream2, Type)>)> {
+    let variant_name = &variant.ident;
+    let mut bindings = Vec::with_capacity(variant.fields.len());
+    let mut values = Vec::with_capacity(variant.fields.len());
+    for (index, field) in variant.fields.iter().enumerate() {
+        let binding = match &field.ident {
+            Some(field_name) => field_name.clone(),
+            None => format_ident!("field_{}", index),
+        };
+        values.push((quote!(#binding), field_ocaml_type(field)?));
+        bindings.push(binding);
+    }
+    let pattern = match &variant.fields {
+        Fields::Named(_) => quote!(Self::#variant_name { #(#bindings),* }),
+        Fields::Unnamed(_) => quote!(Self::#variant_name(#(#bindings),*)),
+        Fields::Unit => quote!(Self::#variant_name),
+    };
+    Ok((pattern, values))
+}
+
+// Converts each value and stores it in the rooted `block`, in order.
+fn expand_field_stores(values: &[(TokenStream2, Type)]) -> TokenStream2 {
+    let stores = values.iter().enumerate().map(|(index, (value, ocaml_typ))| {
+        quote! {
+            let field_val = ::ocaml_interop::ToOCaml::<#ocaml_typ>::to_ocaml(#value, cr).get_raw();
+            ::ocaml_interop::internal::store_raw_field_at(cr, &block, #index, field_val);
+        }
+    });
+    quote!(#(#stores)*)
+}
+
+fn field_ocaml_type(field: &syn::Field) -> syn::Result<Type> {
+    let attrs = OCamlAttrs::parse(&field.attrs)?;
+    if let Some(tag) = attrs.tag {
+        return Err(syn::Error::new(
+            tag.span(),
+            "`tag` is only allowed on enum variants",
+        ));
+    }
+    if let Some(transparent) = attrs.transparent {
+        return Err(syn::Error::new(
+            transparent,
+            "`transparent` is only allowed on structs",
+        ));
+    }
+    if let Some(polymorphic_variant) = attrs.polymorphic_variant {
+        return Err(syn::Error::new(
+            polymorphic_variant,
+            "`polymorphic_variant` is only allowed on enums",
+        ));
+    }
+    Ok(attrs.as_.unwrap_or_else(|| field.ty.clone()))
+}
+
+// Contents of the `#[ocaml(...)]` attributes of an item.
+#[derive(Default)]
+struct OCamlAttrs {
+    as_: Option<Type>,
+    tag: Option<syn::LitInt>,
+    transparent: Option<Span>,
+    polymorphic_variant: Option<Span>,
+}
+
+impl OCamlAttrs {
+    fn parse(attrs: &[Attribute]) -> syn::Result<Self> {
+        let mut result = Self::default();
+        for attr in attrs.iter().filter(|attr| attr.path().is_ident("ocaml")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("as_") {
+                    let value: syn::LitStr = meta.value()?.parse()?;
+                    result.as_ = Some(value.parse()?);
+                    Ok(())
+                } else if meta.path.is_ident("tag") {
+                    result.tag = Some(meta.value()?.parse()?);
+                    Ok(())
+                } else if meta.path.is_ident("transparent") {
+                    result.transparent = Some(meta.path.span());
+                    Ok(())
+                } else if meta.path.is_ident("polymorphic_variant") {
+                    result.polymorphic_variant = Some(meta.path.span());
+                    Ok(())
+                } else {
+                    Err(meta.error("unsupported ocaml attribute"))
+                }
+            })?;
+        }
+        Ok(result)
+    }
+}
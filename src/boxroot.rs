@@ -1,14 +1,22 @@
 // Copyright (c) Viable Systems and TezEdge Contributors
 // SPDX-License-Identifier: MIT
 
-use std::{marker::PhantomData, ops::Deref, sync::Once};
+use std::{
+    cell::RefCell,
+    marker::PhantomData,
+    mem::ManuallyDrop,
+    ops::{Deref, DerefMut},
+    sync::Once,
+};
 
 use ocaml_boxroot_sys::{
     boxroot_create, boxroot_delete, boxroot_get, boxroot_get_ref, boxroot_modify, boxroot_setup,
     BoxRoot as PrimitiveBoxRoot,
 };
 
-use crate::{memory::OCamlCell, OCaml, OCamlRef, OCamlRuntime};
+use crate::{
+    memory::OCamlCell, mlvalues::UNIT, runtime::Generation, OCaml, OCamlRef, OCamlRuntime,
+};
 
 /// `BoxRoot<T>` is a container for a rooted [`OCaml`]`<T>` value.
 pub struct BoxRoot<T: 'static> {
@@ -58,3 +66,88 @@ impl<T> Deref for BoxRoot<T> {
         unsafe { &*(boxroot_get_ref(self.boxroot) as *const OCamlCell<T>) }
     }
 }
+
+/// A pool of reusable roots for values of type `T`.
+///
+/// Creating and deleting a [`BoxRoot`] has a cost that can dominate hot loops. The roots
+/// of a pool are allocated once, and handed out by [`BoxRootPool::root`] as
+/// [`PooledRoot`] guards that return them to the pool when dropped. Guards borrow the
+/// pool, so none of them can outlive it.
+///
+/// When all the roots of the pool are in use, new ones are allocated. These return to
+/// the pool too once released.
+pub struct BoxRootPool<T: 'static> {
+    free: RefCell<Vec<BoxRoot<T>>>,
+}
+
+impl<T> BoxRootPool<T> {
+    /// Creates a pool with `capacity` roots.
+    pub fn new(_cr: &OCamlRuntime, capacity: usize) -> Self {
+        let free = (0..capacity).map(|_| BoxRoot::new(released())).collect();
+        BoxRootPool {
+            free: RefCell::new(free),
+        }
+    }
+
+    /// Returns the number of roots of the pool that are not in use.
+    pub fn available(&self) -> usize {
+        self.free.borrow().len()
+    }
+
+    /// Roots the OCaml value `val` with one of the roots of the pool.
+    pub fn root(&self, val: OCaml<T>) -> PooledRoot<'_, T> {
+        let free_root = self.free.borrow_mut().pop();
+        let root = match free_root {
+            Some(mut root) => {
+                root.keep(val);
+                root
+            }
+            None => BoxRoot::new(val),
+        };
+        PooledRoot {
+            root: ManuallyDrop::new(root),
+            pool: self,
+        }
+    }
+}
+
+/// A root obtained from a [`BoxRootPool`], that returns to the pool when dropped.
+///
+/// It dereferences to a [`BoxRoot`]`<T>`.
+pub struct PooledRoot<'pool, T: 'static> {
+    root: ManuallyDrop<BoxRoot<T>>,
+    pool: &'pool BoxRootPool<T>,
+}
+
+impl<'pool, T> Deref for PooledRoot<'pool, T> {
+    type Target = BoxRoot<T>;
+
+    fn deref(&self) -> &BoxRoot<T> {
+        &self.root
+    }
+}
+
+impl<'pool, T> DerefMut for PooledRoot<'pool, T> {
+    fn deref_mut(&mut self) -> &mut BoxRoot<T> {
+        &mut self.root
+    }
+}
+
+impl<'pool, T> Drop for PooledRoot<'pool, T> {
+    fn drop(&mut self) {
+        let mut root = unsafe { ManuallyDrop::take(&mut self.root) };
+        // The released value must not be kept alive by the pool
+        root.keep(released());
+        self.pool.free.borrow_mut().push(root);
+    }
+}
+
+// Value held by the roots of a pool that are not in use, an immediate value that the
+// GC ignores.
+fn released<T>() -> OCaml<'static, T> {
+    OCaml {
+        _marker: PhantomData,
+        raw: UNIT,
+        generation: Generation::current(),
+    }
+}
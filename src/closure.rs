@@ -74,7 +74,7 @@ impl OCamlClosure {
             let ex = unsafe { OCamlException::of(extract_exception(result)) };
             panic!("OCaml exception, message: {:?}", ex.message())
         } else {
-            unsafe { OCaml::new(cr, result) }
+            unsafe { OCaml::after_safepoint(cr, result) }
         }
     }
 }
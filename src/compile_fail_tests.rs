@@ -43,3 +43,29 @@ pub struct NoStaticDerefsForNonImmediates;
 /// println!("{:?}", slice);
 /// # ()
 pub struct BigarraySliceEscapeCheck;
+
+// Checks that floats can't be stored boxed in an `OCamlArray`, OCaml expects float arrays
+// to hold them unboxed.
+// Must fail with:
+// error[E0277]: the trait bound `OCamlFloat: OCamlArrayElement` is not satisfied
+/// ```compile_fail
+/// # use ocaml_interop::*;
+/// # fn test(cr: &mut OCamlRuntime) {
+/// let floats = vec![1.0f64, 2.0];
+/// let array: BoxRoot<OCamlArray<OCamlFloat>> = floats.to_boxroot(cr);
+/// # }
+/// ```
+pub struct NoBoxedFloatArrays;
+
+// Checks that variant tags outside of the range of block tags are rejected.
+// Must fail with:
+// error: the tag of a non-constant constructor must be between 0 and 245
+/// ```compile_fail
+/// # use ocaml_interop::*;
+/// #[derive(ToOCaml)]
+/// enum Shape {
+///     #[ocaml(tag = -1)]
+///     Circle(#[ocaml(as_ = "OCamlFloat")] f64),
+/// }
+/// ```
+pub struct NoNegativeVariantTags;
@@ -1,8 +1,26 @@
 // Copyright (c) Viable Systems and TezEdge Contributors
 // SPDX-License-Identifier: MIT
 
+#[cfg(feature = "bytes")]
+mod bytes;
+#[cfg(feature = "chrono")]
+mod chrono;
 mod from_ocaml;
+#[cfg(feature = "serde")]
+mod ocaml_serde;
+#[cfg(feature = "serde_json")]
+mod serde_json;
 mod to_ocaml;
 
-pub use self::from_ocaml::FromOCaml;
-pub use self::to_ocaml::ToOCaml;
+pub use self::from_ocaml::{FromOCaml, TryFromOCaml};
+#[cfg(feature = "serde")]
+pub use self::ocaml_serde::{to_ocaml_via_serde, OCamlSerializeError, OCamlSerializer};
+#[cfg(feature = "serde_json")]
+pub use self::serde_json::OCamlJson;
+pub use self::to_ocaml::{
+    display_to_ocaml, flatten_to_ocaml, fold_into_ocaml, instant_to_ocaml, iter_to_tuple2,
+    iter_to_tuple3, iter_to_tuple4, iter_to_tuple5, ocaml_list_from_forward_iter,
+    ocaml_list_from_forward_iter_with_options, ocaml_list_from_iter,
+    ocaml_list_from_iter_with_options, ocaml_list_of_keys, ocaml_list_of_values, ConversionOptions,
+    ListBuilder, ToOCaml, ToOCamlOrRaise, TryToOCaml,
+};
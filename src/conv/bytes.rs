@@ -0,0 +1,39 @@
+// Copyright (c) Viable Systems and TezEdge Contributors
+// SPDX-License-Identifier: MIT
+
+use ::bytes::{Bytes, BytesMut};
+
+use crate::{
+    memory::alloc_bytes, mlvalues::OCamlBytes, runtime::OCamlRuntime, value::OCaml, ToOCaml,
+};
+
+/// Copies the buffer into a new OCaml `bytes` value.
+///
+/// For large buffers the copy can be avoided by converting into a bigarray instead: a
+/// `Vec::from(bytes)` reuses the allocation when the buffer is not shared, and the
+/// vector can then be handed to OCaml with
+/// [`alloc_bigarray1_from_vec`](crate::alloc_bigarray1_from_vec).
+unsafe impl ToOCaml<OCamlBytes> for Bytes {
+    fn to_ocaml<'a>(&self, cr: &'a mut OCamlRuntime) -> OCaml<'a, OCamlBytes> {
+        alloc_bytes(cr, self.as_ref())
+    }
+}
+
+unsafe impl ToOCaml<OCamlBytes> for &Bytes {
+    fn to_ocaml<'a>(&self, cr: &'a mut OCamlRuntime) -> OCaml<'a, OCamlBytes> {
+        (*self).to_ocaml(cr)
+    }
+}
+
+/// Copies the buffer into a new OCaml `bytes` value.
+unsafe impl ToOCaml<OCamlBytes> for BytesMut {
+    fn to_ocaml<'a>(&self, cr: &'a mut OCamlRuntime) -> OCaml<'a, OCamlBytes> {
+        alloc_bytes(cr, self.as_ref())
+    }
+}
+
+unsafe impl ToOCaml<OCamlBytes> for &BytesMut {
+    fn to_ocaml<'a>(&self, cr: &'a mut OCamlRuntime) -> OCaml<'a, OCamlBytes> {
+        (*self).to_ocaml(cr)
+    }
+}
@@ -0,0 +1,41 @@
+// Copyright (c) Viable Systems and TezEdge Contributors
+// SPDX-License-Identifier: MIT
+
+use ::chrono::{DateTime, TimeZone};
+
+use crate::{
+    memory::{alloc_double, alloc_int64},
+    mlvalues::{OCamlFloat, OCamlInt64},
+    runtime::OCamlRuntime,
+    value::OCaml,
+    ToOCaml,
+};
+
+/// Converts a `DateTime` into an OCaml `float` number of seconds since the Unix epoch,
+/// like the values returned by `Unix.gettimeofday`.
+///
+/// Times before the epoch are converted into negative numbers. A `float` can't hold every
+/// nanosecond: for present-day dates the fractional part is only precise to about a
+/// quarter of a microsecond, and that precision decreases further away from the epoch.
+/// Convert into `OCamlInt64` instead to keep the exact number of nanoseconds.
+unsafe impl<Tz: TimeZone> ToOCaml<OCamlFloat> for DateTime<Tz> {
+    fn to_ocaml<'a>(&self, cr: &'a mut OCamlRuntime) -> OCaml<'a, OCamlFloat> {
+        let secs = self.timestamp() as f64 + f64::from(self.timestamp_subsec_nanos()) / 1e9;
+        alloc_double(cr, secs)
+    }
+}
+
+/// Converts a `DateTime` into an OCaml `Int64.t` number of nanoseconds since the Unix
+/// epoch.
+///
+/// Times before the epoch are converted into negative numbers. The conversion is exact,
+/// but panics for times more than about 292 years away from the epoch, which don't fit
+/// in an `Int64.t`.
+unsafe impl<Tz: TimeZone> ToOCaml<OCamlInt64> for DateTime<Tz> {
+    fn to_ocaml<'a>(&self, cr: &'a mut OCamlRuntime) -> OCaml<'a, OCamlInt64> {
+        let nanos = self
+            .timestamp_nanos_opt()
+            .unwrap_or_else(|| panic!("DateTime doesn't fit in Int64.t nanoseconds: {:?}", self));
+        alloc_int64(cr, nanos)
+    }
+}
@@ -1,8 +1,18 @@
 // Copyright (c) Viable Systems and TezEdge Contributors
 // SPDX-License-Identifier: MIT
 
+use core::convert::TryFrom;
+use core::ops::ControlFlow;
+use core::str::{self, Utf8Error};
+use std::collections::{HashMap, LinkedList, VecDeque};
+use std::hash::Hash;
+
 use crate::{
-    mlvalues::{field_val, OCamlBytes, OCamlFloat, OCamlInt, OCamlInt32, OCamlInt64, OCamlList},
+    error::OCamlException,
+    mlvalues::{
+        field_val, OCamlBytes, OCamlChar, OCamlControlFlow, OCamlExn, OCamlFloat, OCamlInt,
+        OCamlInt32, OCamlInt64, OCamlList, OCamlNativeInt,
+    },
     value::OCaml,
 };
 
@@ -12,6 +22,23 @@ pub unsafe trait FromOCaml<T> {
     fn from_ocaml(v: OCaml<T>) -> Self;
 }
 
+/// Implements fallible conversion from OCaml values into Rust values.
+///
+/// This is meant for OCaml values that can't always be represented as a Rust value of this
+/// type, like OCaml strings (which can hold any sequence of bytes) read as Rust strings.
+///
+/// # Safety
+///
+/// As with [`FromOCaml`], implementations must only read the OCaml value in ways that are
+/// valid for the type `T`.
+pub unsafe trait TryFromOCaml<T>: Sized {
+    /// The type returned when the conversion fails.
+    type Error;
+
+    /// Convert from OCaml value.
+    fn try_from_ocaml(v: OCaml<T>) -> Result<Self, Self::Error>;
+}
+
 unsafe impl FromOCaml<()> for () {
     fn from_ocaml(_v: OCaml<()>) -> Self {
         // Nothing, just unit
@@ -44,12 +71,25 @@ unsafe impl FromOCaml<OCamlInt64> for i64 {
     }
 }
 
+unsafe impl FromOCaml<OCamlNativeInt> for isize {
+    fn from_ocaml(v: OCaml<OCamlNativeInt>) -> Self {
+        let val = unsafe { field_val(v.raw(), 1) };
+        unsafe { *(val as *const isize) }
+    }
+}
+
 unsafe impl FromOCaml<bool> for bool {
     fn from_ocaml(v: OCaml<bool>) -> Self {
         v.to_bool()
     }
 }
 
+unsafe impl FromOCaml<OCamlChar> for char {
+    fn from_ocaml(v: OCaml<OCamlChar>) -> Self {
+        v.to_char()
+    }
+}
+
 unsafe impl FromOCaml<OCamlFloat> for f64 {
     fn from_ocaml(v: OCaml<OCamlFloat>) -> Self {
         unsafe { *(v.raw() as *const f64) }
@@ -65,12 +105,32 @@ unsafe impl FromOCaml<String> for Vec<u8> {
     }
 }
 
+/// Invalid UTF-8 sequences are replaced with `U+FFFD`. Use [`TryFromOCaml`] instead to
+/// detect them.
 unsafe impl FromOCaml<String> for String {
     fn from_ocaml(v: OCaml<String>) -> Self {
         String::from_utf8_lossy(v.as_bytes()).into_owned()
     }
 }
 
+/// Fails with the UTF-8 error if the string is not valid UTF-8.
+unsafe impl TryFromOCaml<String> for String {
+    type Error = Utf8Error;
+
+    fn try_from_ocaml(v: OCaml<String>) -> Result<Self, Self::Error> {
+        str::from_utf8(v.as_bytes()).map(str::to_owned)
+    }
+}
+
+/// Fails with the UTF-8 error if the bytes are not valid UTF-8.
+unsafe impl TryFromOCaml<OCamlBytes> for String {
+    type Error = Utf8Error;
+
+    fn try_from_ocaml(v: OCaml<OCamlBytes>) -> Result<Self, Self::Error> {
+        str::from_utf8(v.as_bytes()).map(str::to_owned)
+    }
+}
+
 unsafe impl FromOCaml<OCamlBytes> for Vec<u8> {
     fn from_ocaml(v: OCaml<OCamlBytes>) -> Self {
         let raw_bytes = v.as_bytes();
@@ -87,12 +147,46 @@ unsafe impl FromOCaml<OCamlBytes> for Box<[u8]> {
     }
 }
 
+macro_rules! int128_from_ocaml {
+    ($($t:ty),+) => {
+        $(
+            /// Decodes an integer from 16 bytes in big-endian order (most significant byte
+            /// first), the encoding used by the [`ToOCaml`](crate::ToOCaml) implementation.
+            ///
+            /// # Panics
+            ///
+            /// Panics if the length of the `bytes` is not 16.
+            unsafe impl FromOCaml<OCamlBytes> for $t {
+                fn from_ocaml(v: OCaml<OCamlBytes>) -> Self {
+                    let bytes = v.as_bytes();
+                    let bytes = <[u8; 16]>::try_from(bytes).unwrap_or_else(|_| {
+                        panic!("expected 16 bytes, got {} bytes", bytes.len())
+                    });
+                    <$t>::from_be_bytes(bytes)
+                }
+            }
+        )+
+    };
+}
+
+int128_from_ocaml!(u128, i128);
+
 unsafe impl FromOCaml<OCamlBytes> for String {
     fn from_ocaml(v: OCaml<OCamlBytes>) -> Self {
         unsafe { v.as_str_unchecked() }.to_owned()
     }
 }
 
+/// Converts an OCaml exception, capturing its name and printed form.
+///
+/// With the `Result` conversion this reads a `('a, exn) result`, like
+/// `Error (Failure "boom")`, into a `Result<A, OCamlException>`.
+unsafe impl FromOCaml<OCamlExn> for OCamlException {
+    fn from_ocaml(v: OCaml<OCamlExn>) -> Self {
+        unsafe { OCamlException::of(v.raw()) }
+    }
+}
+
 unsafe impl<OCamlT, T: FromOCaml<OCamlT>> FromOCaml<OCamlT> for Box<T> {
     fn from_ocaml(v: OCaml<OCamlT>) -> Self {
         Box::new(T::from_ocaml(v))
@@ -112,11 +206,28 @@ where
     }
 }
 
+unsafe impl<B, OCamlB, C, OCamlC> FromOCaml<OCamlControlFlow<OCamlB, OCamlC>> for ControlFlow<B, C>
+where
+    B: FromOCaml<OCamlB>,
+    C: FromOCaml<OCamlC>,
+{
+    fn from_ocaml(v: OCaml<OCamlControlFlow<OCamlB, OCamlC>>) -> Self {
+        match v.to_control_flow() {
+            ControlFlow::Continue(ocaml_continue) => {
+                ControlFlow::Continue(C::from_ocaml(ocaml_continue))
+            }
+            ControlFlow::Break(ocaml_break) => ControlFlow::Break(B::from_ocaml(ocaml_break)),
+        }
+    }
+}
+
 unsafe impl<A, OCamlA> FromOCaml<Option<OCamlA>> for Option<A>
 where
     A: FromOCaml<OCamlA>,
 {
     fn from_ocaml(v: OCaml<Option<OCamlA>>) -> Self {
+        // `None` is an immediate value, `to_option` checks for it before reading the
+        // field of the `Some` block, and the payload is only converted for `Some`.
         v.to_option().map(A::from_ocaml)
     }
 }
@@ -137,6 +248,72 @@ where
     }
 }
 
+macro_rules! list_from_ocaml {
+    ($($container:ident),+) => {
+        $(
+            unsafe impl<A, OCamlA> FromOCaml<OCamlList<OCamlA>> for $container<A>
+            where
+                A: FromOCaml<OCamlA>,
+            {
+                fn from_ocaml(v: OCaml<OCamlList<OCamlA>>) -> Self {
+                    let mut container = $container::new();
+                    let mut current = v;
+                    while let Some((hd, tl)) = current.uncons() {
+                        current = tl;
+                        container.push_back(A::from_ocaml(hd));
+                    }
+                    container
+                }
+            }
+        )+
+    };
+}
+
+list_from_ocaml!(VecDeque, LinkedList);
+
+/// Converts an OCaml list of exactly `N` elements into an array.
+///
+/// # Panics
+///
+/// Panics if the length of the list is not `N`.
+unsafe impl<A, OCamlA, const N: usize> FromOCaml<OCamlList<OCamlA>> for [A; N]
+where
+    A: FromOCaml<OCamlA>,
+{
+    fn from_ocaml(v: OCaml<OCamlList<OCamlA>>) -> Self {
+        let vec = Vec::<A>::from_ocaml(v);
+        <[A; N]>::try_from(vec).unwrap_or_else(|vec| {
+            panic!(
+                "expected an OCaml list of length {}, got a list of length {}",
+                N,
+                vec.len()
+            )
+        })
+    }
+}
+
+/// Converts an OCaml association list into a `HashMap`.
+///
+/// When a key is repeated in the list, the map will contain the value of its last binding.
+/// Note that this differs from `List.assoc` in OCaml, which finds the first binding.
+unsafe impl<K, V, OCamlK: 'static, OCamlV: 'static> FromOCaml<OCamlList<(OCamlK, OCamlV)>>
+    for HashMap<K, V>
+where
+    K: FromOCaml<OCamlK> + Eq + Hash,
+    V: FromOCaml<OCamlV>,
+{
+    fn from_ocaml(v: OCaml<OCamlList<(OCamlK, OCamlV)>>) -> Self {
+        let mut map = HashMap::new();
+        let mut current = v;
+        while let Some((hd, tl)) = current.uncons() {
+            current = tl;
+            let (key, value) = <(K, V)>::from_ocaml(hd);
+            map.insert(key, value);
+        }
+        map
+    }
+}
+
 // Tuples
 
 macro_rules! tuple_from_ocaml {
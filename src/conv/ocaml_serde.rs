@@ -0,0 +1,573 @@
+// Copyright (c) Viable Systems and TezEdge Contributors
+// SPDX-License-Identifier: MIT
+
+use ::serde::ser::{self, Serialize};
+use core::fmt;
+use ocaml_sys::caml_hash_variant;
+use std::ffi::CString;
+
+use crate::{
+    error::{OCamlCharConversionError, OCamlFixnumConversionError},
+    memory::{
+        alloc_bytes, alloc_cons, alloc_double, alloc_float_record, alloc_poly_variant, alloc_some,
+        alloc_string, alloc_tuple, store_raw_field_at,
+    },
+    mlvalues::{tag, OCamlFloat, OCamlList, RawOCaml, UNIT},
+    runtime::OCamlRuntime,
+    value::OCaml,
+    BoxRoot,
+};
+
+/// Error returned when a value can't be represented by the [`OCamlSerializer`].
+#[derive(Debug)]
+pub enum OCamlSerializeError {
+    /// An integer doesn't fit in an OCaml int.
+    Fixnum(OCamlFixnumConversionError),
+    /// A character is outside of the range of OCaml chars.
+    Char(OCamlCharConversionError),
+    /// Error reported by the `Serialize` implementation, or a value the serializer
+    /// doesn't support (like 128 bit integers).
+    Custom(String),
+}
+
+impl fmt::Display for OCamlSerializeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            OCamlSerializeError::Fixnum(err) => write!(f, "{}", err),
+            OCamlSerializeError::Char(err) => write!(f, "{}", err),
+            OCamlSerializeError::Custom(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+impl std::error::Error for OCamlSerializeError {}
+
+impl ser::Error for OCamlSerializeError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        OCamlSerializeError::Custom(msg.to_string())
+    }
+}
+
+/// A [`serde::Serializer`] that builds OCaml values.
+///
+/// The serde data model is mapped into OCaml values like this:
+///
+/// - `bool`s into `bool`, integers into `int`, and floats into `float`. Integers that
+///   don't fit in an OCaml int are an error.
+/// - `char`s into `char`, characters above 255 are an error.
+/// - strings into `string`, and byte arrays into `bytes`.
+/// - options into `option`, and units and unit structs into `()`.
+/// - newtype structs into the value they wrap.
+/// - sequences into lists, and maps into association lists of `(key, value)` pairs.
+/// - tuples, tuple structs and structs into tuples and records, with the fields in
+///   declaration order. Like in OCaml, structs whose fields are all floats are stored
+///   flat, as a block of unboxed doubles, tuples never are.
+/// - enums into polymorphic variants, named like the Rust variant. Variants with more
+///   than one field take a tuple of the fields as argument.
+///
+/// Enums are not converted into regular OCaml variants because their tags depend on the
+/// position of the constructor among the constant or non-constant constructors, which
+/// serde doesn't provide.
+///
+/// Values are usually converted through [`to_ocaml_via_serde`] instead of using the
+/// serializer directly.
+pub struct OCamlSerializer<'a> {
+    cr: &'a mut OCamlRuntime,
+}
+
+impl<'a> OCamlSerializer<'a> {
+    /// Creates a serializer that allocates its values in the OCaml runtime `cr`.
+    pub fn new(cr: &'a mut OCamlRuntime) -> Self {
+        OCamlSerializer { cr }
+    }
+
+    fn reborrow(&mut self) -> OCamlSerializer<'_> {
+        OCamlSerializer { cr: self.cr }
+    }
+}
+
+/// Converts any `Serialize` value into an OCaml value (see [`OCamlSerializer`] for the
+/// representation used).
+///
+/// ```
+/// # use ocaml_interop::*;
+/// # fn example(cr: &mut OCamlRuntime) {
+/// let values = vec![(1, "one".to_owned()), (2, "two".to_owned())];
+/// let list: OCaml<OCamlList<(OCamlInt, String)>> =
+///     unsafe { to_ocaml_via_serde(cr, &values) }.unwrap();
+/// # }
+/// ```
+///
+/// # Safety
+///
+/// Nothing checks that the value built from `value` has the shape of the OCaml type
+/// `OCamlT`. The caller must make sure that the representation described in
+/// [`OCamlSerializer`] of `T` is a valid value of `OCamlT`: same fields in the same order,
+/// same variant names, and the same types for each of them. In particular, a struct
+/// whose fields are all floats can only be the representation of an OCaml record whose
+/// fields are all `float`, and not of a record with fields of other types that hold
+/// floats. Converting or accessing a
+/// value that doesn't match its type is undefined behaviour.
+pub unsafe fn to_ocaml_via_serde<'a, T, OCamlT>(
+    cr: &'a mut OCamlRuntime,
+    value: &T,
+) -> Result<OCaml<'a, OCamlT>, OCamlSerializeError>
+where
+    T: Serialize + ?Sized,
+{
+    let root = value.serialize(OCamlSerializer::new(cr))?;
+    Ok(OCaml::new(cr, root.get_raw()))
+}
+
+fn root_raw(cr: &OCamlRuntime, raw: RawOCaml) -> BoxRoot<()> {
+    BoxRoot::new(unsafe { OCaml::new(cr, raw) })
+}
+
+fn variant_hash(variant: &'static str) -> RawOCaml {
+    let name = CString::new(variant).expect("variant names can't contain NUL bytes");
+    unsafe { caml_hash_variant(name.as_ptr() as *const u8) }
+}
+
+fn list_from_roots(cr: &mut OCamlRuntime, items: Vec<BoxRoot<()>>) -> BoxRoot<()> {
+    let mut list: BoxRoot<OCamlList<()>> = BoxRoot::new(OCaml::nil());
+    for item in items.iter().rev() {
+        let cons = alloc_cons(cr, item, &list);
+        list.keep(cons);
+    }
+    root_raw(cr, unsafe { list.get_raw() })
+}
+
+impl<'a> ser::Serializer for OCamlSerializer<'a> {
+    type Ok = BoxRoot<()>;
+    type Error = OCamlSerializeError;
+    type SerializeSeq = SerializeList<'a>;
+    type SerializeTuple = SerializeBlock<'a>;
+    type SerializeTupleStruct = SerializeBlock<'a>;
+    type SerializeTupleVariant = SerializeVariant<'a>;
+    type SerializeMap = SerializeAssoc<'a>;
+    type SerializeStruct = SerializeBlock<'a>;
+    type SerializeStructVariant = SerializeVariant<'a>;
+
+    fn serialize_bool(self, v: bool) -> Result<Self::Ok, Self::Error> {
+        Ok(root_raw(self.cr, unsafe { OCaml::of_bool(v).raw() }))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Self::Ok, Self::Error> {
+        self.serialize_i64(v.into())
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Self::Ok, Self::Error> {
+        self.serialize_i64(v.into())
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Self::Ok, Self::Error> {
+        self.serialize_i64(v.into())
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Self::Ok, Self::Error> {
+        let n = OCaml::of_i64(v).map_err(OCamlSerializeError::Fixnum)?;
+        Ok(root_raw(self.cr, unsafe { n.raw() }))
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Self::Ok, Self::Error> {
+        self.serialize_i64(v.into())
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Self::Ok, Self::Error> {
+        self.serialize_i64(v.into())
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Self::Ok, Self::Error> {
+        self.serialize_i64(v.into())
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Self::Ok, Self::Error> {
+        let n = OCaml::of_u64(v).map_err(OCamlSerializeError::Fixnum)?;
+        Ok(root_raw(self.cr, unsafe { n.raw() }))
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<Self::Ok, Self::Error> {
+        self.serialize_f64(v.into())
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<Self::Ok, Self::Error> {
+        let raw = unsafe { alloc_double(self.cr, v).raw() };
+        Ok(root_raw(self.cr, raw))
+    }
+
+    fn serialize_char(self, v: char) -> Result<Self::Ok, Self::Error> {
+        let c = OCaml::of_char(v).map_err(OCamlSerializeError::Char)?;
+        Ok(root_raw(self.cr, unsafe { c.raw() }))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Self::Ok, Self::Error> {
+        let raw = unsafe { alloc_string(self.cr, v).raw() };
+        Ok(root_raw(self.cr, raw))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Self::Ok, Self::Error> {
+        let raw = unsafe { alloc_bytes(self.cr, v).raw() };
+        Ok(root_raw(self.cr, raw))
+    }
+
+    fn serialize_none(self) -> Result<Self::Ok, Self::Error> {
+        Ok(root_raw(self.cr, unsafe {
+            OCaml::<Option<()>>::none().raw()
+        }))
+    }
+
+    fn serialize_some<T>(mut self, value: &T) -> Result<Self::Ok, Self::Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        let value = value.serialize(self.reborrow())?;
+        let raw = unsafe { alloc_some(self.cr, &value).raw() };
+        Ok(root_raw(self.cr, raw))
+    }
+
+    fn serialize_unit(self) -> Result<Self::Ok, Self::Error> {
+        Ok(root_raw(self.cr, UNIT))
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Self::Ok, Self::Error> {
+        self.serialize_unit()
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<Self::Ok, Self::Error> {
+        Ok(root_raw(self.cr, variant_hash(variant)))
+    }
+
+    fn serialize_newtype_struct<T>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok, Self::Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T>(
+        mut self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok, Self::Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        let payload = value.serialize(self.reborrow())?;
+        let raw = unsafe { alloc_poly_variant(self.cr, variant_hash(variant), &payload).raw() };
+        Ok(root_raw(self.cr, raw))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq, Self::Error> {
+        Ok(SerializeList {
+            cr: self.cr,
+            items: Vec::with_capacity(len.unwrap_or(0)),
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple, Self::Error> {
+        Ok(SerializeBlock::new(self.cr, len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleStruct, Self::Error> {
+        Ok(SerializeBlock::new(self.cr, len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleVariant, Self::Error> {
+        Ok(SerializeVariant::new(self.cr, variant, len))
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
+        Ok(SerializeAssoc {
+            cr: self.cr,
+            entries: Vec::with_capacity(len.unwrap_or(0)),
+            key: None,
+        })
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStruct, Self::Error> {
+        Ok(SerializeBlock::new(self.cr, len))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStructVariant, Self::Error> {
+        Ok(SerializeVariant::new(self.cr, variant, len))
+    }
+}
+
+/// Builds an OCaml list from a serde sequence.
+pub struct SerializeList<'a> {
+    cr: &'a mut OCamlRuntime,
+    items: Vec<BoxRoot<()>>,
+}
+
+impl<'a> ser::SerializeSeq for SerializeList<'a> {
+    type Ok = BoxRoot<()>;
+    type Error = OCamlSerializeError;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<(), Self::Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        let item = value.serialize(OCamlSerializer::new(self.cr))?;
+        self.items.push(item);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        Ok(list_from_roots(self.cr, self.items))
+    }
+}
+
+/// Builds an OCaml tuple or record from a serde tuple or struct.
+pub struct SerializeBlock<'a> {
+    cr: &'a mut OCamlRuntime,
+    block: BoxRoot<()>,
+    len: usize,
+    index: usize,
+}
+
+impl<'a> SerializeBlock<'a> {
+    fn new(cr: &'a mut OCamlRuntime, len: usize) -> Self {
+        // Tuples and structs without fields are represented by `()`
+        let block = if len == 0 {
+            root_raw(cr, UNIT)
+        } else {
+            BoxRoot::new(unsafe { alloc_tuple(cr, len) })
+        };
+        SerializeBlock {
+            cr,
+            block,
+            len,
+            index: 0,
+        }
+    }
+
+    fn store<T>(&mut self, value: &T) -> Result<(), OCamlSerializeError>
+    where
+        T: Serialize + ?Sized,
+    {
+        if self.index >= self.len {
+            return Err(OCamlSerializeError::Custom(format!(
+                "expected {} fields, got more",
+                self.len
+            )));
+        }
+        let field = value.serialize(OCamlSerializer::new(self.cr))?;
+        unsafe { store_raw_field_at(self.cr, &self.block, self.index, field.get_raw()) };
+        self.index += 1;
+        Ok(())
+    }
+
+    fn finish(self) -> Result<(&'a mut OCamlRuntime, BoxRoot<()>), OCamlSerializeError> {
+        if self.index < self.len {
+            return Err(OCamlSerializeError::Custom(format!(
+                "expected {} fields, got {}",
+                self.len, self.index
+            )));
+        }
+        Ok((self.cr, self.block))
+    }
+}
+
+impl<'a> ser::SerializeTuple for SerializeBlock<'a> {
+    type Ok = BoxRoot<()>;
+    type Error = OCamlSerializeError;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<(), Self::Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.store(value)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        self.finish().map(|(_, block)| block)
+    }
+}
+
+impl<'a> ser::SerializeTupleStruct for SerializeBlock<'a> {
+    type Ok = BoxRoot<()>;
+    type Error = OCamlSerializeError;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<(), Self::Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.store(value)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        self.finish().map(|(_, block)| block)
+    }
+}
+
+impl<'a> ser::SerializeStruct for SerializeBlock<'a> {
+    type Ok = BoxRoot<()>;
+    type Error = OCamlSerializeError;
+
+    fn serialize_field<T>(&mut self, _key: &'static str, value: &T) -> Result<(), Self::Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.store(value)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        let len = self.len;
+        let (cr, block) = self.finish()?;
+        Ok(flatten_float_record(cr, block, len))
+    }
+}
+
+// OCaml stores records whose fields are all floats flat, as unboxed doubles
+fn flatten_float_record(cr: &mut OCamlRuntime, block: BoxRoot<()>, len: usize) -> BoxRoot<()> {
+    let record = cr.get(&block);
+    let mut fields = Vec::with_capacity(len);
+    for i in 0..len {
+        let field = unsafe { record.field::<()>(i) }.raw();
+        if !unsafe { ocaml_sys::is_block(field) && ocaml_sys::tag_val(field) == tag::DOUBLE } {
+            return block;
+        }
+        let field: OCaml<OCamlFloat> = unsafe { OCaml::new(cr, field) };
+        fields.push(field.to_rust::<f64>());
+    }
+    if fields.is_empty() {
+        return block;
+    }
+    let raw = unsafe { alloc_float_record::<()>(cr, &fields).raw() };
+    root_raw(cr, raw)
+}
+
+/// Builds an OCaml polymorphic variant from a serde tuple or struct variant.
+pub struct SerializeVariant<'a> {
+    fields: SerializeBlock<'a>,
+    hash: RawOCaml,
+}
+
+impl<'a> SerializeVariant<'a> {
+    fn new(cr: &'a mut OCamlRuntime, variant: &'static str, len: usize) -> Self {
+        SerializeVariant {
+            fields: SerializeBlock::new(cr, len),
+            hash: variant_hash(variant),
+        }
+    }
+
+    fn finish(self) -> Result<BoxRoot<()>, OCamlSerializeError> {
+        let len = self.fields.len;
+        let (cr, fields) = self.fields.finish()?;
+        let raw = match len {
+            0 => self.hash,
+            // A single field is the argument itself, not a tuple of one element
+            1 => {
+                let payload = root_raw(cr, unsafe { cr.get(&fields).field::<()>(0).raw() });
+                unsafe { alloc_poly_variant(cr, self.hash, &payload).raw() }
+            }
+            _ => unsafe { alloc_poly_variant(cr, self.hash, &fields).raw() },
+        };
+        Ok(root_raw(cr, raw))
+    }
+}
+
+impl<'a> ser::SerializeTupleVariant for SerializeVariant<'a> {
+    type Ok = BoxRoot<()>;
+    type Error = OCamlSerializeError;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<(), Self::Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.fields.store(value)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        self.finish()
+    }
+}
+
+impl<'a> ser::SerializeStructVariant for SerializeVariant<'a> {
+    type Ok = BoxRoot<()>;
+    type Error = OCamlSerializeError;
+
+    fn serialize_field<T>(&mut self, _key: &'static str, value: &T) -> Result<(), Self::Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.fields.store(value)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        self.finish()
+    }
+}
+
+/// Builds an OCaml association list from a serde map.
+pub struct SerializeAssoc<'a> {
+    cr: &'a mut OCamlRuntime,
+    entries: Vec<BoxRoot<()>>,
+    key: Option<BoxRoot<()>>,
+}
+
+impl<'a> ser::SerializeMap for SerializeAssoc<'a> {
+    type Ok = BoxRoot<()>;
+    type Error = OCamlSerializeError;
+
+    fn serialize_key<T>(&mut self, key: &T) -> Result<(), Self::Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.key = Some(key.serialize(OCamlSerializer::new(self.cr))?);
+        Ok(())
+    }
+
+    fn serialize_value<T>(&mut self, value: &T) -> Result<(), Self::Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        let key = self
+            .key
+            .take()
+            .ok_or_else(|| OCamlSerializeError::Custom("map value without a key".to_owned()))?;
+        let value = value.serialize(OCamlSerializer::new(self.cr))?;
+        let pair: BoxRoot<()> = BoxRoot::new(unsafe { alloc_tuple(self.cr, 2) });
+        unsafe {
+            store_raw_field_at(self.cr, &pair, 0, key.get_raw());
+            store_raw_field_at(self.cr, &pair, 1, value.get_raw());
+        }
+        self.entries.push(pair);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        Ok(list_from_roots(self.cr, self.entries))
+    }
+}
@@ -0,0 +1,109 @@
+// Copyright (c) Viable Systems and TezEdge Contributors
+// SPDX-License-Identifier: MIT
+
+use ::serde_json::{Map, Number, Value};
+
+use crate::{
+    memory::{alloc_variant, store_raw_field_at},
+    mlvalues::{OCamlArrayElement, OCamlFloat, OCamlInt, OCamlList},
+    runtime::OCamlRuntime,
+    value::OCaml,
+    BoxRoot, ToOCaml,
+};
+
+/// [`OCaml`]`<OCamlJson>` is a reference to a JSON value of the following OCaml type:
+///
+/// ```ocaml
+/// type t =
+///   | Null
+///   | Bool of bool
+///   | Int of int
+///   | Float of float
+///   | String of string
+///   | List of t list
+///   | Assoc of (string * t) list
+/// ```
+///
+/// JSON numbers that are integers and fit in an OCaml int are converted into `Int`, all
+/// the others into `Float`, which loses precision: integers above 2^53 are rounded to the
+/// nearest float (`u64::MAX` becomes `1.8446744073709552e19`), and numbers outside of the
+/// range of floats, which `serde_json` only keeps with its `arbitrary_precision` feature,
+/// become infinities.
+pub struct OCamlJson {}
+
+unsafe impl OCamlArrayElement for OCamlJson {}
+
+const TAG_BOOL: u8 = 0;
+const TAG_INT: u8 = 1;
+const TAG_FLOAT: u8 = 2;
+const TAG_STRING: u8 = 3;
+const TAG_LIST: u8 = 4;
+const TAG_ASSOC: u8 = 5;
+
+// Allocates a constructor of `OCamlJson` with a single argument.
+fn alloc_json_variant<'a, A, OCamlA>(
+    cr: &'a mut OCamlRuntime,
+    tag: u8,
+    value: &A,
+) -> OCaml<'a, OCamlJson>
+where
+    A: ToOCaml<OCamlA>,
+{
+    unsafe {
+        let block: BoxRoot<OCamlJson> = BoxRoot::new(alloc_variant(cr, tag, 1));
+        let field_val = value.to_ocaml(cr).get_raw();
+        store_raw_field_at(cr, &block, 0, field_val);
+        cr.get(&block)
+    }
+}
+
+// Numbers that don't fit in an OCaml int are converted into floats.
+fn number_to_ocaml<'a>(cr: &'a mut OCamlRuntime, n: &Number) -> OCaml<'a, OCamlJson> {
+    match n.as_i64() {
+        Some(i) if OCaml::of_i64(i).is_ok() => alloc_json_variant::<_, OCamlInt>(cr, TAG_INT, &i),
+        _ => {
+            // `as_f64` fails for numbers that overflow a float, parsing them rounds them
+            // to an infinity instead
+            let f = n.as_f64().unwrap_or_else(|| {
+                n.to_string()
+                    .parse()
+                    .expect("JSON numbers are valid float literals")
+            });
+            alloc_json_variant::<_, OCamlFloat>(cr, TAG_FLOAT, &f)
+        }
+    }
+}
+
+/// Converts a JSON value into the OCaml [`OCamlJson`] type.
+///
+/// JSON numbers are converted into `Int` when they are integers that fit in an OCaml int,
+/// and into `Float` otherwise, possibly losing precision (see [`OCamlJson`]). Object
+/// members are converted into an `Assoc` list, in the iteration order of the object.
+unsafe impl ToOCaml<OCamlJson> for Value {
+    fn to_ocaml<'a>(&self, cr: &'a mut OCamlRuntime) -> OCaml<'a, OCamlJson> {
+        match self {
+            Value::Null => unsafe { OCaml::new(cr, OCaml::of_i64_unchecked(0).raw()) },
+            Value::Bool(b) => alloc_json_variant::<_, bool>(cr, TAG_BOOL, b),
+            Value::Number(n) => number_to_ocaml(cr, n),
+            Value::String(s) => alloc_json_variant::<_, String>(cr, TAG_STRING, s),
+            Value::Array(values) => {
+                alloc_json_variant::<_, OCamlList<OCamlJson>>(cr, TAG_LIST, values)
+            }
+            Value::Object(members) => {
+                alloc_json_variant::<_, OCamlList<(String, OCamlJson)>>(cr, TAG_ASSOC, members)
+            }
+        }
+    }
+}
+
+unsafe impl ToOCaml<OCamlJson> for &Value {
+    fn to_ocaml<'a>(&self, cr: &'a mut OCamlRuntime) -> OCaml<'a, OCamlJson> {
+        (*self).to_ocaml(cr)
+    }
+}
+
+unsafe impl ToOCaml<OCamlList<(String, OCamlJson)>> for Map<String, Value> {
+    fn to_ocaml<'a>(&self, cr: &'a mut OCamlRuntime) -> OCaml<'a, OCamlList<(String, OCamlJson)>> {
+        crate::ocaml_list_from_iter(cr, self.iter())
+    }
+}
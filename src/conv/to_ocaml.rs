@@ -1,22 +1,47 @@
 // Copyright (c) Viable Systems and TezEdge Contributors
 // SPDX-License-Identifier: MIT
 
-use core::{borrow::Borrow, str};
+use core::{
+    borrow::Borrow,
+    cell::{Cell, RefCell},
+    convert::TryFrom,
+    fmt::{Display, Write},
+    hash::BuildHasher,
+    marker::PhantomData,
+    num::{NonZeroI32, NonZeroI64, NonZeroU32, NonZeroU64, NonZeroUsize, Saturating, Wrapping},
+    ops::{ControlFlow, Range, RangeInclusive},
+    str,
+};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
+use std::ffi::{CStr, OsStr, OsString};
+use std::net::Ipv4Addr;
+#[cfg(unix)]
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
+use std::rc::{Rc, Weak as RcWeak};
+use std::sync::{Arc, Weak as ArcWeak};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 use crate::{
     memory::{
-        alloc_bigarray1, alloc_bytes, alloc_cons, alloc_double, alloc_error, alloc_int32,
-        alloc_int64, alloc_ok, alloc_some, alloc_string, alloc_tuple, store_raw_field_at, OCamlRef,
+        alloc_array, alloc_bigarray1, alloc_bigarray2, alloc_bytes, alloc_cons,
+        alloc_cons_placeholder, alloc_double, alloc_error, alloc_float_array, alloc_int32,
+        alloc_int64, alloc_nativeint, alloc_ok, alloc_some, alloc_string, alloc_tuple,
+        alloc_variant, raise_failure_raw, store_field_no_barrier, store_raw_field_at, OCamlRef,
     },
     mlvalues::{
-        bigarray::{Array1, BigarrayElt},
-        OCamlBytes, OCamlFloat, OCamlInt, OCamlInt32, OCamlInt64, OCamlList, RawOCaml, FALSE, NONE,
-        TRUE,
+        bigarray::{Array1, Array2, BigarrayElt, Matrix},
+        tag, tag_val, wosize_val, OCamlArray, OCamlArrayElement, OCamlBytes, OCamlChar,
+        OCamlControlFlow, OCamlFloat, OCamlFloatArray, OCamlInt, OCamlInt32, OCamlInt64, OCamlList,
+        OCamlNativeInt, RawOCaml, FALSE, NONE, TRUE, UNIT,
     },
     runtime::OCamlRuntime,
     value::OCaml,
-    BoxRoot,
+    BoxRoot, OCamlException, OCamlFixnumConversionError, OCamlFloatConversionError,
+    OCamlStringConversionError,
 };
+use ocaml_sys::caml_gc_minor;
 
 /// Implements conversion from Rust values into OCaml values.
 pub unsafe trait ToOCaml<T> {
@@ -29,6 +54,59 @@ pub unsafe trait ToOCaml<T> {
     fn to_ocaml<'a>(&self, cr: &'a mut OCamlRuntime) -> OCaml<'a, T>;
 }
 
+/// Implements conversion from Rust `Result` values into OCaml values, raising an
+/// OCaml `Failure` exception for errors.
+///
+/// This is meant for Rust functions called from OCaml that signal errors with exceptions
+/// instead of returning an OCaml `result`.
+pub trait ToOCamlOrRaise<T> {
+    /// Convert the `Ok` value into an OCaml value, or raise an OCaml `Failure` exception
+    /// with the message of the `Err` value.
+    ///
+    /// # Safety
+    ///
+    /// Raising the exception has the same requirements as [`raise_failure`](crate::raise_failure).
+    unsafe fn to_ocaml_or_raise<'a>(&self, cr: &'a mut OCamlRuntime) -> OCaml<'a, T>;
+}
+
+impl<A, OCamlA, E> ToOCamlOrRaise<OCamlA> for Result<A, E>
+where
+    A: ToOCaml<OCamlA>,
+    E: Display,
+{
+    unsafe fn to_ocaml_or_raise<'a>(&self, cr: &'a mut OCamlRuntime) -> OCaml<'a, OCamlA> {
+        match self {
+            Ok(value) => value.to_ocaml(cr),
+            Err(err) => {
+                // The message is dropped before raising, it would be leaked otherwise
+                let message = alloc_string(cr, &err.to_string()).get_raw();
+                raise_failure_raw(message)
+            }
+        }
+    }
+}
+
+/// Implements fallible conversion from Rust values into OCaml values.
+///
+/// This is meant for values that can't always be represented as an OCaml value of type `T`.
+///
+/// # Safety
+///
+/// Implementations must only succeed with OCaml values that are valid for the type `T`,
+/// as with [`ToOCaml`].
+pub unsafe trait TryToOCaml<T> {
+    /// The type returned when the conversion fails.
+    type Error;
+
+    /// Convert to OCaml value. Return an already rooted value as [`BoxRoot`]`<T>`.
+    fn try_to_boxroot(&self, cr: &mut OCamlRuntime) -> Result<BoxRoot<T>, Self::Error> {
+        self.try_to_ocaml(cr).map(BoxRoot::new)
+    }
+
+    /// Convert to OCaml value.
+    fn try_to_ocaml<'a>(&self, cr: &'a mut OCamlRuntime) -> Result<OCaml<'a, T>, Self::Error>;
+}
+
 unsafe impl<'root, T> ToOCaml<T> for OCamlRef<'root, T> {
     fn to_ocaml<'a>(&self, cr: &'a mut OCamlRuntime) -> OCaml<'a, T> {
         unsafe { OCaml::new(cr, self.get_raw()) }
@@ -47,18 +125,96 @@ unsafe impl ToOCaml<()> for () {
     }
 }
 
+/// Converts a `PhantomData` marker into OCaml `unit`.
+unsafe impl<X> ToOCaml<()> for PhantomData<X> {
+    fn to_ocaml(&self, _cr: &mut OCamlRuntime) -> OCaml<'static, ()> {
+        OCaml::unit()
+    }
+}
+
+/// Converts an `i64` into an OCaml `int`.
+///
+/// OCaml ints are one bit smaller than `i64` (63 bits on 64-bit platforms), values that
+/// don't fit are silently truncated (losing their top bit). Convert through [`TryToOCaml`]
+/// to get an error for those instead.
 unsafe impl ToOCaml<OCamlInt> for i64 {
     fn to_ocaml<'a>(&self, cr: &'a mut OCamlRuntime) -> OCaml<'a, OCamlInt> {
         unsafe { OCaml::new(cr, ((self << 1) | 1i64) as RawOCaml) }
     }
 }
 
+unsafe impl TryToOCaml<OCamlInt> for i64 {
+    type Error = OCamlFixnumConversionError;
+
+    fn try_to_ocaml<'a>(
+        &self,
+        _cr: &'a mut OCamlRuntime,
+    ) -> Result<OCaml<'a, OCamlInt>, OCamlFixnumConversionError> {
+        OCaml::of_i64(*self)
+    }
+}
+
+unsafe impl TryToOCaml<OCamlInt> for u64 {
+    type Error = OCamlFixnumConversionError;
+
+    fn try_to_ocaml<'a>(
+        &self,
+        _cr: &'a mut OCamlRuntime,
+    ) -> Result<OCaml<'a, OCamlInt>, OCamlFixnumConversionError> {
+        OCaml::of_u64(*self)
+    }
+}
+
+unsafe impl TryToOCaml<OCamlInt> for usize {
+    type Error = OCamlFixnumConversionError;
+
+    fn try_to_ocaml<'a>(
+        &self,
+        _cr: &'a mut OCamlRuntime,
+    ) -> Result<OCaml<'a, OCamlInt>, OCamlFixnumConversionError> {
+        OCaml::of_usize(*self)
+    }
+}
+
 unsafe impl ToOCaml<OCamlInt> for i32 {
     fn to_ocaml<'a>(&self, cr: &'a mut OCamlRuntime) -> OCaml<'a, OCamlInt> {
         (*self as i64).to_ocaml(cr)
     }
 }
 
+unsafe impl ToOCaml<OCamlInt> for u8 {
+    fn to_ocaml<'a>(&self, cr: &'a mut OCamlRuntime) -> OCaml<'a, OCamlInt> {
+        (*self as i64).to_ocaml(cr)
+    }
+}
+
+unsafe impl ToOCaml<OCamlInt> for u16 {
+    fn to_ocaml<'a>(&self, cr: &'a mut OCamlRuntime) -> OCaml<'a, OCamlInt> {
+        (*self as i64).to_ocaml(cr)
+    }
+}
+
+unsafe impl ToOCaml<OCamlInt> for i8 {
+    fn to_ocaml<'a>(&self, cr: &'a mut OCamlRuntime) -> OCaml<'a, OCamlInt> {
+        (*self as i64).to_ocaml(cr)
+    }
+}
+
+unsafe impl ToOCaml<OCamlInt> for i16 {
+    fn to_ocaml<'a>(&self, cr: &'a mut OCamlRuntime) -> OCaml<'a, OCamlInt> {
+        (*self as i64).to_ocaml(cr)
+    }
+}
+
+// No implementations are provided for `u64` and `usize` because their values
+// may not fit in an OCaml fixnum, [`OCaml::of_u64`] and [`OCaml::of_usize`]
+// perform a checked conversion instead.
+unsafe impl ToOCaml<OCamlInt> for u32 {
+    fn to_ocaml<'a>(&self, cr: &'a mut OCamlRuntime) -> OCaml<'a, OCamlInt> {
+        (*self as i64).to_ocaml(cr)
+    }
+}
+
 unsafe impl ToOCaml<OCamlInt32> for i32 {
     fn to_ocaml<'a>(&self, cr: &'a mut OCamlRuntime) -> OCaml<'a, OCamlInt32> {
         alloc_int32(cr, *self)
@@ -71,18 +227,149 @@ unsafe impl ToOCaml<OCamlInt64> for i64 {
     }
 }
 
+unsafe impl ToOCaml<OCamlNativeInt> for isize {
+    fn to_ocaml<'a>(&self, cr: &'a mut OCamlRuntime) -> OCaml<'a, OCamlNativeInt> {
+        alloc_nativeint(cr, *self)
+    }
+}
+
+macro_rules! int128_to_ocaml {
+    ($($t:ty),+) => {
+        $(
+            /// Converts the integer into 16 bytes, in big-endian order (most significant
+            /// byte first).
+            ///
+            /// On the OCaml side the value can be decoded from the `bytes`, for example
+            /// with `Bytes.get_int64_be b 0` for the high half and `Bytes.get_int64_be b 8`
+            /// for the low half.
+            unsafe impl ToOCaml<OCamlBytes> for $t {
+                fn to_ocaml<'a>(&self, cr: &'a mut OCamlRuntime) -> OCaml<'a, OCamlBytes> {
+                    alloc_bytes(cr, &self.to_be_bytes())
+                }
+            }
+        )+
+    };
+}
+
+int128_to_ocaml!(u128, i128);
+
+/// Converts an `f64` into an OCaml `float`.
+///
+/// NaN and infinite values are converted as they are. Convert through [`TryToOCaml`]
+/// to get an error for those instead.
 unsafe impl ToOCaml<OCamlFloat> for f64 {
     fn to_ocaml<'a>(&self, cr: &'a mut OCamlRuntime) -> OCaml<'a, OCamlFloat> {
         alloc_double(cr, *self)
     }
 }
 
+unsafe impl TryToOCaml<OCamlFloat> for f64 {
+    type Error = OCamlFloatConversionError;
+
+    fn try_to_ocaml<'a>(
+        &self,
+        cr: &'a mut OCamlRuntime,
+    ) -> Result<OCaml<'a, OCamlFloat>, OCamlFloatConversionError> {
+        if self.is_finite() {
+            Ok(alloc_double(cr, *self))
+        } else {
+            Err(OCamlFloatConversionError::NotFinite(*self))
+        }
+    }
+}
+
+/// Converts an `f32` into an OCaml `float`.
+///
+/// The value is widened to `f64` first, which is lossless.
+unsafe impl ToOCaml<OCamlFloat> for f32 {
+    fn to_ocaml<'a>(&self, cr: &'a mut OCamlRuntime) -> OCaml<'a, OCamlFloat> {
+        alloc_double(cr, f64::from(*self))
+    }
+}
+
+/// Converts a `Duration` into an OCaml `float` number of seconds.
+///
+/// A `float` can't represent every duration exactly, precision is lost for durations
+/// that have a nanoseconds part and are longer than about 104 days. Convert into
+/// `OCamlInt64` instead to keep the exact number of nanoseconds.
+unsafe impl ToOCaml<OCamlFloat> for Duration {
+    fn to_ocaml<'a>(&self, cr: &'a mut OCamlRuntime) -> OCaml<'a, OCamlFloat> {
+        alloc_double(cr, self.as_secs_f64())
+    }
+}
+
+/// Converts a `Duration` into an OCaml `Int64.t` number of nanoseconds.
+///
+/// The conversion is exact, but panics for durations longer than about 292 years,
+/// which don't fit in an `Int64.t`.
+unsafe impl ToOCaml<OCamlInt64> for Duration {
+    fn to_ocaml<'a>(&self, cr: &'a mut OCamlRuntime) -> OCaml<'a, OCamlInt64> {
+        let nanos = i64::try_from(self.as_nanos())
+            .unwrap_or_else(|_| panic!("Duration doesn't fit in Int64.t nanoseconds: {:?}", self));
+        alloc_int64(cr, nanos)
+    }
+}
+
+/// Converts a `SystemTime` into an OCaml `float` number of seconds since the Unix epoch,
+/// like the values returned by `Unix.gettimeofday`.
+///
+/// Times before the epoch are converted into negative numbers.
+unsafe impl ToOCaml<OCamlFloat> for SystemTime {
+    fn to_ocaml<'a>(&self, cr: &'a mut OCamlRuntime) -> OCaml<'a, OCamlFloat> {
+        let secs = match self.duration_since(UNIX_EPOCH) {
+            Ok(since_epoch) => since_epoch.as_secs_f64(),
+            Err(err) => -err.duration().as_secs_f64(),
+        };
+        alloc_double(cr, secs)
+    }
+}
+
+/// Converts a `SystemTime` into an OCaml `Int64.t` number of nanoseconds since the Unix
+/// epoch.
+///
+/// Times before the epoch are converted into negative numbers. The conversion is exact,
+/// but panics for times more than about 292 years away from the epoch, which don't fit
+/// in an `Int64.t`.
+unsafe impl ToOCaml<OCamlInt64> for SystemTime {
+    fn to_ocaml<'a>(&self, cr: &'a mut OCamlRuntime) -> OCaml<'a, OCamlInt64> {
+        let nanos = match self.duration_since(UNIX_EPOCH) {
+            Ok(since_epoch) => i64::try_from(since_epoch.as_nanos()).ok(),
+            Err(err) => i64::try_from(err.duration().as_nanos())
+                .ok()
+                .map(|nanos| -nanos),
+        };
+        let nanos = nanos
+            .unwrap_or_else(|| panic!("SystemTime doesn't fit in Int64.t nanoseconds: {:?}", self));
+        alloc_int64(cr, nanos)
+    }
+}
+
 unsafe impl ToOCaml<bool> for bool {
     fn to_ocaml<'a>(&self, cr: &'a mut OCamlRuntime) -> OCaml<'a, bool> {
         unsafe { OCaml::new(cr, if *self { TRUE } else { FALSE }) }
     }
 }
 
+/// Converts a `char` into an OCaml `char`.
+///
+/// # Panics
+///
+/// Panics if the code point is greater than `U+00FF`, because OCaml chars are a single byte.
+/// [`OCaml::of_char`] can be used instead to get an error in such cases.
+unsafe impl ToOCaml<OCamlChar> for char {
+    fn to_ocaml<'a>(&self, _cr: &'a mut OCamlRuntime) -> OCaml<'a, OCamlChar> {
+        OCaml::of_char(*self).unwrap_or_else(|err| panic!("{}", err))
+    }
+}
+
+/// Converts a `char` into a single-character OCaml `string`, encoded as UTF-8.
+unsafe impl ToOCaml<String> for char {
+    fn to_ocaml<'a>(&self, cr: &'a mut OCamlRuntime) -> OCaml<'a, String> {
+        let mut buf = [0; 4];
+        alloc_string(cr, self.encode_utf8(&mut buf))
+    }
+}
+
 // TODO: figure out how to implement all this without so much duplication
 // it is not as simple as implementing for Borrow<str/[u8]> because
 // of the Box<T> implementation bellow, which causes a trait implementation
@@ -136,6 +423,102 @@ unsafe impl ToOCaml<OCamlBytes> for Vec<u8> {
     }
 }
 
+// Non-zero integers, converted like the underlying integer type
+
+macro_rules! non_zero_to_ocaml {
+    ($($t:ty => $($ot:ty),+);+ $(;)?) => {
+        $($(
+            unsafe impl ToOCaml<$ot> for $t {
+                fn to_ocaml<'a>(&self, cr: &'a mut OCamlRuntime) -> OCaml<'a, $ot> {
+                    self.get().to_ocaml(cr)
+                }
+            }
+        )+)+
+    };
+}
+
+non_zero_to_ocaml!(
+    NonZeroI32 => OCamlInt, OCamlInt32;
+    NonZeroU32 => OCamlInt;
+    NonZeroI64 => OCamlInt, OCamlInt64;
+);
+
+unsafe impl TryToOCaml<OCamlInt> for NonZeroI64 {
+    type Error = OCamlFixnumConversionError;
+
+    fn try_to_ocaml<'a>(
+        &self,
+        cr: &'a mut OCamlRuntime,
+    ) -> Result<OCaml<'a, OCamlInt>, OCamlFixnumConversionError> {
+        self.get().try_to_ocaml(cr)
+    }
+}
+
+unsafe impl TryToOCaml<OCamlInt> for NonZeroU64 {
+    type Error = OCamlFixnumConversionError;
+
+    fn try_to_ocaml<'a>(
+        &self,
+        cr: &'a mut OCamlRuntime,
+    ) -> Result<OCaml<'a, OCamlInt>, OCamlFixnumConversionError> {
+        self.get().try_to_ocaml(cr)
+    }
+}
+
+/// Converts an IPv4 address into an OCaml `Int32.t`.
+///
+/// The octets are packed in network (big-endian) order, the first octet being the most
+/// significant byte: `127.0.0.1` is converted into `0x7F000001l`. Addresses from
+/// `128.0.0.0` above are converted into negative numbers.
+unsafe impl ToOCaml<OCamlInt32> for Ipv4Addr {
+    fn to_ocaml<'a>(&self, cr: &'a mut OCamlRuntime) -> OCaml<'a, OCamlInt32> {
+        alloc_int32(cr, u32::from_be_bytes(self.octets()) as i32)
+    }
+}
+
+/// Converts an IPv4 address into an OCaml string in dotted-decimal notation.
+unsafe impl ToOCaml<String> for Ipv4Addr {
+    fn to_ocaml<'a>(&self, cr: &'a mut OCamlRuntime) -> OCaml<'a, String> {
+        self.to_string().to_ocaml(cr)
+    }
+}
+
+// References
+//
+// A blanket implementation for `&A` would conflict with the one for `OCamlRef`, so
+// conversions from references are implemented explicitly for the most common types.
+// This makes it possible to mix owned and borrowed values in the components of
+// tuples, for example. For other types, the reference has to be dereferenced (or
+// the value cloned) before the conversion.
+
+macro_rules! ref_to_ocaml {
+    ($($t:ty => $($ot:ty),+);+ $(;)?) => {
+        $($(
+            unsafe impl ToOCaml<$ot> for &$t {
+                fn to_ocaml<'a>(&self, cr: &'a mut OCamlRuntime) -> OCaml<'a, $ot> {
+                    (*self).to_ocaml(cr)
+                }
+            }
+        )+)+
+    };
+}
+
+ref_to_ocaml!(
+    i64 => OCamlInt, OCamlInt64;
+    i32 => OCamlInt, OCamlInt32;
+    u8 => OCamlInt;
+    u16 => OCamlInt;
+    u32 => OCamlInt;
+    i8 => OCamlInt;
+    i16 => OCamlInt;
+    isize => OCamlNativeInt;
+    f64 => OCamlFloat;
+    f32 => OCamlFloat;
+    bool => bool;
+    char => OCamlChar, String;
+    String => String, OCamlBytes;
+);
+
 unsafe impl ToOCaml<OCamlBytes> for Box<[u8]> {
     fn to_ocaml<'a>(&self, cr: &'a mut OCamlRuntime) -> OCaml<'a, OCamlBytes> {
         let slice: &[u8] = self;
@@ -150,6 +533,114 @@ unsafe impl ToOCaml<Array1<u8>> for Box<[u8]> {
     }
 }
 
+unsafe impl<'b> ToOCaml<String> for Cow<'b, str> {
+    fn to_ocaml<'a>(&self, cr: &'a mut OCamlRuntime) -> OCaml<'a, String> {
+        self.as_ref().to_ocaml(cr)
+    }
+}
+
+unsafe impl<'b> ToOCaml<OCamlBytes> for Cow<'b, str> {
+    fn to_ocaml<'a>(&self, cr: &'a mut OCamlRuntime) -> OCaml<'a, OCamlBytes> {
+        self.as_ref().to_ocaml(cr)
+    }
+}
+
+unsafe impl<'b> ToOCaml<String> for Cow<'b, [u8]> {
+    fn to_ocaml<'a>(&self, cr: &'a mut OCamlRuntime) -> OCaml<'a, String> {
+        self.as_ref().to_ocaml(cr)
+    }
+}
+
+unsafe impl<'b> ToOCaml<OCamlBytes> for Cow<'b, [u8]> {
+    fn to_ocaml<'a>(&self, cr: &'a mut OCamlRuntime) -> OCaml<'a, OCamlBytes> {
+        self.as_ref().to_ocaml(cr)
+    }
+}
+
+/// Converts the elements of a borrowed or owned slice into an OCaml list, in the same order.
+unsafe impl<'b, A, OCamlA: 'static> ToOCaml<OCamlList<OCamlA>> for Cow<'b, [A]>
+where
+    A: Clone + ToOCaml<OCamlA>,
+{
+    fn to_ocaml<'a>(&self, cr: &'a mut OCamlRuntime) -> OCaml<'a, OCamlList<OCamlA>> {
+        match self {
+            Cow::Borrowed(slice) => slice.to_ocaml(cr),
+            Cow::Owned(vec) => vec.to_ocaml(cr),
+        }
+    }
+}
+
+// C strings are converted without their terminating NUL. A `CStr` can't contain
+// interior NULs, so the converted value holds every byte that precedes the terminator.
+
+unsafe impl ToOCaml<String> for &CStr {
+    fn to_ocaml<'a>(&self, cr: &'a mut OCamlRuntime) -> OCaml<'a, String> {
+        self.to_bytes().to_ocaml(cr)
+    }
+}
+
+unsafe impl ToOCaml<OCamlBytes> for &CStr {
+    fn to_ocaml<'a>(&self, cr: &'a mut OCamlRuntime) -> OCaml<'a, OCamlBytes> {
+        self.to_bytes().to_ocaml(cr)
+    }
+}
+
+// Platform strings and paths
+//
+// On Unix, OS strings are arbitrary byte sequences, just like OCaml strings, and are
+// converted as they are. On other platforms (like Windows), OS strings are converted
+// only if they are valid Unicode, converting them with `to_ocaml` panics otherwise,
+// and `try_to_ocaml` returns an error instead.
+
+#[cfg(unix)]
+fn os_str_to_ocaml<'a>(
+    cr: &'a mut OCamlRuntime,
+    s: &OsStr,
+) -> Result<OCaml<'a, String>, OCamlStringConversionError> {
+    Ok(s.as_bytes().to_ocaml(cr))
+}
+
+#[cfg(not(unix))]
+fn os_str_to_ocaml<'a>(
+    cr: &'a mut OCamlRuntime,
+    s: &OsStr,
+) -> Result<OCaml<'a, String>, OCamlStringConversionError> {
+    match s.to_str() {
+        Some(s) => Ok(s.to_ocaml(cr)),
+        None => Err(OCamlStringConversionError::InvalidUnicode(s.to_owned())),
+    }
+}
+
+macro_rules! os_str_to_ocaml {
+    ($($t:ty),+) => {
+        $(
+            unsafe impl ToOCaml<String> for $t {
+                fn to_ocaml<'a>(&self, cr: &'a mut OCamlRuntime) -> OCaml<'a, String> {
+                    os_str_to_ocaml(cr, self.as_ref()).unwrap_or_else(|err| panic!("{}", err))
+                }
+            }
+
+            unsafe impl TryToOCaml<String> for $t {
+                type Error = OCamlStringConversionError;
+
+                fn try_to_ocaml<'a>(
+                    &self,
+                    cr: &'a mut OCamlRuntime,
+                ) -> Result<OCaml<'a, String>, Self::Error> {
+                    os_str_to_ocaml(cr, self.as_ref())
+                }
+            }
+        )+
+    };
+}
+
+os_str_to_ocaml!(&OsStr, OsString, &Path, PathBuf);
+
+/// Converts the boxed value.
+///
+/// This applies to owned boxes too, so `Box<A>` can be used wherever `A` is expected to
+/// implement [`ToOCaml`], for example as the elements of a `Vec<Box<A>>`. Conversions
+/// never consume their input, the boxed value is converted in place.
 unsafe impl<A, OCamlA> ToOCaml<OCamlA> for Box<A>
 where
     A: ToOCaml<OCamlA>,
@@ -159,6 +650,140 @@ where
     }
 }
 
+macro_rules! num_wrapper_to_ocaml {
+    ($($wrapper:ident),+) => {
+        $(
+            /// Converts the wrapped number, like `Wrapping(5i64)` into an [`OCamlInt`] or
+            /// an [`OCamlInt64`].
+            unsafe impl<A, OCamlA> ToOCaml<OCamlA> for $wrapper<A>
+            where
+                A: ToOCaml<OCamlA>,
+            {
+                fn to_ocaml<'a>(&self, cr: &'a mut OCamlRuntime) -> OCaml<'a, OCamlA> {
+                    self.0.to_ocaml(cr)
+                }
+            }
+        )+
+    };
+}
+
+num_wrapper_to_ocaml!(Wrapping, Saturating);
+
+unsafe impl<A, OCamlA> ToOCaml<OCamlA> for &mut A
+where
+    A: ToOCaml<OCamlA>,
+{
+    fn to_ocaml<'a>(&self, cr: &'a mut OCamlRuntime) -> OCaml<'a, OCamlA> {
+        (**self).to_ocaml(cr)
+    }
+}
+
+unsafe impl<A, OCamlA> ToOCaml<OCamlA> for Rc<A>
+where
+    A: ToOCaml<OCamlA>,
+{
+    fn to_ocaml<'a>(&self, cr: &'a mut OCamlRuntime) -> OCaml<'a, OCamlA> {
+        self.as_ref().to_ocaml(cr)
+    }
+}
+
+unsafe impl<A, OCamlA> ToOCaml<OCamlA> for &Rc<A>
+where
+    A: ToOCaml<OCamlA>,
+{
+    fn to_ocaml<'a>(&self, cr: &'a mut OCamlRuntime) -> OCaml<'a, OCamlA> {
+        self.as_ref().to_ocaml(cr)
+    }
+}
+
+unsafe impl<A, OCamlA> ToOCaml<OCamlA> for Arc<A>
+where
+    A: ToOCaml<OCamlA>,
+{
+    fn to_ocaml<'a>(&self, cr: &'a mut OCamlRuntime) -> OCaml<'a, OCamlA> {
+        self.as_ref().to_ocaml(cr)
+    }
+}
+
+unsafe impl<A, OCamlA> ToOCaml<OCamlA> for &Arc<A>
+where
+    A: ToOCaml<OCamlA>,
+{
+    fn to_ocaml<'a>(&self, cr: &'a mut OCamlRuntime) -> OCaml<'a, OCamlA> {
+        self.as_ref().to_ocaml(cr)
+    }
+}
+
+macro_rules! cell_to_ocaml {
+    ($($cell:ty),+) => {
+        $(
+            /// Converts the value currently held by the `RefCell`.
+            ///
+            /// This is a snapshot, the OCaml value is not updated by later changes to the
+            /// cell.
+            ///
+            /// # Panics
+            ///
+            /// Panics if the value is currently mutably borrowed.
+            unsafe impl<A, OCamlA> ToOCaml<OCamlA> for $cell
+            where
+                A: ToOCaml<OCamlA>,
+            {
+                fn to_ocaml<'a>(&self, cr: &'a mut OCamlRuntime) -> OCaml<'a, OCamlA> {
+                    let value = RefCell::borrow(self);
+                    ToOCaml::<OCamlA>::to_ocaml(&*value, cr)
+                }
+            }
+        )+
+    };
+}
+
+cell_to_ocaml!(RefCell<A>, &RefCell<A>);
+
+macro_rules! copy_cell_to_ocaml {
+    ($($cell:ty),+) => {
+        $(
+            /// Converts the value currently held by the `Cell`.
+            ///
+            /// This is a snapshot, the OCaml value is not updated by later changes to the
+            /// cell.
+            unsafe impl<A, OCamlA> ToOCaml<OCamlA> for $cell
+            where
+                A: ToOCaml<OCamlA> + Copy,
+            {
+                fn to_ocaml<'a>(&self, cr: &'a mut OCamlRuntime) -> OCaml<'a, OCamlA> {
+                    Cell::get(self).to_ocaml(cr)
+                }
+            }
+        )+
+    };
+}
+
+copy_cell_to_ocaml!(Cell<A>, &Cell<A>);
+
+macro_rules! weak_to_ocaml {
+    ($($weak:ty),+) => {
+        $(
+            /// Converts a weak reference into an OCaml `option`, `Some` with the value if it
+            /// is still alive, `None` otherwise.
+            unsafe impl<A, OCamlA: 'static> ToOCaml<Option<OCamlA>> for $weak
+            where
+                A: ToOCaml<OCamlA>,
+            {
+                fn to_ocaml<'a>(&self, cr: &'a mut OCamlRuntime) -> OCaml<'a, Option<OCamlA>> {
+                    self.upgrade().to_ocaml(cr)
+                }
+            }
+        )+
+    };
+}
+
+weak_to_ocaml!(RcWeak<A>, ArcWeak<A>);
+
+/// Converts an optional value into an OCaml `option`.
+///
+/// This also covers optional references, like the values returned by `HashMap::get`,
+/// when references to the value type can be converted.
 unsafe impl<A, OCamlA: 'static> ToOCaml<Option<OCamlA>> for Option<A>
 where
     A: ToOCaml<OCamlA>,
@@ -193,26 +818,685 @@ where
     }
 }
 
-unsafe impl<A, OCamlA: 'static> ToOCaml<OCamlList<OCamlA>> for Vec<A>
+/// Converts an `Instant` into an OCaml `float` number of seconds elapsed since `base`.
+///
+/// `Instant` values have no absolute representation, they only make sense relative to
+/// each other, so a base must be chosen, for example the time at which the program
+/// started. `instant` is expected to be later than `base`, when it is earlier the
+/// result is `0.0`.
+pub fn instant_to_ocaml<'a>(
+    cr: &'a mut OCamlRuntime,
+    instant: Instant,
+    base: Instant,
+) -> OCaml<'a, OCamlFloat> {
+    alloc_double(cr, instant.saturating_duration_since(base).as_secs_f64())
+}
+
+/// Converts a nested `Result` into a single OCaml `result`.
+///
+/// `Ok(Ok(value))` becomes `Ok value`, both `Ok(Err(error))` and `Err(error)` become
+/// `Error error`: the inner and outer errors are collapsed into the same OCaml error
+/// type, and the OCaml side can't tell which of the two levels failed.
+///
+/// ```
+/// # use ocaml_interop::*;
+/// # fn example(cr: &mut OCamlRuntime) {
+/// let nested: Result<Result<i64, String>, String> = Ok(Err("inner".to_owned()));
+/// let result: OCaml<Result<OCamlInt, String>> = flatten_to_ocaml(cr, &nested);
+/// # }
+/// ```
+pub fn flatten_to_ocaml<'a, A, OCamlA, Err, OCamlErr>(
+    cr: &'a mut OCamlRuntime,
+    result: &Result<Result<A, Err>, Err>,
+) -> OCaml<'a, Result<OCamlA, OCamlErr>>
 where
     A: ToOCaml<OCamlA>,
+    Err: ToOCaml<OCamlErr>,
+    OCamlA: 'static,
+    OCamlErr: 'static,
 {
-    fn to_ocaml<'a>(&self, cr: &'a mut OCamlRuntime) -> OCaml<'a, OCamlList<OCamlA>> {
-        let mut result = BoxRoot::new(OCaml::nil());
-        for elt in self.iter().rev() {
-            let ov = elt.to_boxroot(cr);
-            let cons = alloc_cons(cr, &ov, &result);
-            result.keep(cons);
+    match result {
+        Ok(Ok(value)) => {
+            let ocaml_value = value.to_boxroot(cr);
+            alloc_ok(cr, &ocaml_value)
+        }
+        Ok(Err(error)) | Err(error) => {
+            let ocaml_error = error.to_boxroot(cr);
+            alloc_error(cr, &ocaml_error)
         }
-        cr.get(&result)
     }
 }
 
-// Tuples
-
-macro_rules! tuple_to_ocaml {
-    ($($n:tt: $t:ident => $ot:ident),+) => {
-        unsafe impl<$($t),+, $($ot: 'static),+> ToOCaml<($($ot),+)> for ($($t),+)
+/// Converts a `ControlFlow` into an OCaml variant, see [`OCamlControlFlow`].
+unsafe impl<B, OCamlB: 'static, C, OCamlC: 'static> ToOCaml<OCamlControlFlow<OCamlB, OCamlC>>
+    for ControlFlow<B, C>
+where
+    B: ToOCaml<OCamlB>,
+    C: ToOCaml<OCamlC>,
+{
+    fn to_ocaml<'a>(
+        &self,
+        cr: &'a mut OCamlRuntime,
+    ) -> OCaml<'a, OCamlControlFlow<OCamlB, OCamlC>> {
+        match self {
+            ControlFlow::Continue(value) => {
+                let value = value.to_boxroot(cr);
+                alloc_single_field_variant(cr, tag::TAG_CONTINUE, &value)
+            }
+            ControlFlow::Break(value) => {
+                let value = value.to_boxroot(cr);
+                alloc_single_field_variant(cr, tag::TAG_BREAK, &value)
+            }
+        }
+    }
+}
+
+fn alloc_single_field_variant<'a, T, F>(
+    cr: &'a mut OCamlRuntime,
+    tag: u8,
+    field: OCamlRef<F>,
+) -> OCaml<'a, T> {
+    unsafe {
+        let block = alloc_variant::<T>(cr, tag, 1).get_raw();
+        store_field_no_barrier(block, 0, field.get_raw());
+        OCaml::new(cr, block)
+    }
+}
+
+/// Converts a slice into an OCaml list, in the same order.
+///
+/// Slices of pairs convert into OCaml association lists, `&[(K, V)]` into
+/// `OCamlList<(OCamlK, OCamlV)>`, without building a map first.
+///
+/// Elements that have more than one OCaml representation, like `i64` (an OCaml `int` or
+/// `Int64.t`), can be converted into lists of any of them, so the list type must be given.
+/// A `&[bool]` for example converts into an `OCamlList<bool>` (an OCaml `bool list`):
+///
+/// ```
+/// # use ocaml_interop::*;
+/// # fn example(cr: &mut OCamlRuntime) {
+/// let bits: &[bool] = &[true, false, true];
+/// let ocaml_bits: BoxRoot<OCamlList<bool>> = bits.to_boxroot(cr);
+/// # }
+/// ```
+///
+/// Slices of options, like sparse columns, convert through this same implementation into
+/// lists of options, `&[Option<A>]` into `OCamlList<Option<OCamlA>>` (an OCaml
+/// `a option list`). A separate implementation for them would overlap with this one.
+unsafe impl<A, OCamlA: 'static> ToOCaml<OCamlList<OCamlA>> for &[A]
+where
+    A: ToOCaml<OCamlA>,
+{
+    fn to_ocaml<'a>(&self, cr: &'a mut OCamlRuntime) -> OCaml<'a, OCamlList<OCamlA>> {
+        list_from_rev_iter::<A, OCamlA, _>(cr, self.iter().rev())
+    }
+}
+
+/// Options for the helpers that convert many elements at once, like
+/// [`ocaml_list_from_iter_with_options`].
+///
+/// By default the conversion runs without interruption, and the GC only runs when the
+/// minor heap gets full. Converting very large structures in one go lets a lot of work
+/// pile up for the GC, forcing periodic minor collections with
+/// [`ConversionOptions::minor_gc_every`] spreads that work over the conversion. Each
+/// collection has a fixed cost, so small intervals reduce the throughput of the
+/// conversion in exchange for a smaller memory footprint.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ConversionOptions {
+    minor_gc_interval: Option<NonZeroUsize>,
+}
+
+impl ConversionOptions {
+    /// Options matching the behaviour of the helpers without options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Forces a minor collection every `interval` converted elements, or never when
+    /// `interval` is 0.
+    pub fn minor_gc_every(self, interval: usize) -> Self {
+        ConversionOptions {
+            minor_gc_interval: NonZeroUsize::new(interval),
+        }
+    }
+
+    // Called after converting each element, `pending` counts the elements converted
+    // since the last collection.
+    fn safepoint(&self, pending: &mut usize) {
+        if let Some(interval) = self.minor_gc_interval {
+            *pending += 1;
+            if *pending == interval.get() {
+                unsafe { caml_gc_minor(UNIT) };
+                *pending = 0;
+            }
+        }
+    }
+}
+
+// Builds an OCaml list from elements produced last to first.
+fn list_from_rev_iter<'a, A, OCamlA, B>(
+    cr: &'a mut OCamlRuntime,
+    elts: impl Iterator<Item = B>,
+) -> OCaml<'a, OCamlList<OCamlA>>
+where
+    A: ToOCaml<OCamlA>,
+    B: Borrow<A>,
+    OCamlA: 'static,
+{
+    list_from_rev_iter_with_options::<A, OCamlA, B>(cr, elts, ConversionOptions::default())
+}
+
+fn list_from_rev_iter_with_options<'a, A, OCamlA, B>(
+    cr: &'a mut OCamlRuntime,
+    elts: impl Iterator<Item = B>,
+    options: ConversionOptions,
+) -> OCaml<'a, OCamlList<OCamlA>>
+where
+    A: ToOCaml<OCamlA>,
+    B: Borrow<A>,
+    OCamlA: 'static,
+{
+    // Only the list being built is rooted. Each cons cell is allocated before the
+    // element it holds is converted, and the element is stored in it right after
+    // conversion, with no allocation in between.
+    let mut result = BoxRoot::new(OCaml::nil());
+    let mut pending = 0;
+    for elt in elts {
+        let cons = unsafe { alloc_cons_placeholder(cr, &result) };
+        result.keep(cons);
+        unsafe {
+            let ov = elt.borrow().to_ocaml(cr).get_raw();
+            store_raw_field_at(cr, &result, 0, ov);
+        }
+        options.safepoint(&mut pending);
+    }
+    cr.get(&result)
+}
+
+/// Builds an OCaml list from the elements of an iterator, without collecting them first.
+///
+/// The elements are consumed from the back, so the iterator must be double-ended. For
+/// iterators that can only be traversed forward use [`ocaml_list_from_forward_iter`].
+///
+/// ```
+/// # use ocaml_interop::*;
+/// # fn example(cr: &mut OCamlRuntime) {
+/// let squares: OCaml<OCamlList<OCamlInt>> =
+///     ocaml_list_from_iter(cr, (1..=10i64).map(|n| n * n));
+/// # }
+/// ```
+pub fn ocaml_list_from_iter<'a, I, OCamlA>(
+    cr: &'a mut OCamlRuntime,
+    iter: I,
+) -> OCaml<'a, OCamlList<OCamlA>>
+where
+    I: IntoIterator,
+    I::IntoIter: DoubleEndedIterator,
+    I::Item: ToOCaml<OCamlA>,
+    OCamlA: 'static,
+{
+    list_from_rev_iter::<I::Item, OCamlA, _>(cr, iter.into_iter().rev())
+}
+
+/// Builds an OCaml list from the elements of a forward-only iterator.
+///
+/// OCaml lists are built starting from their last element, so the elements are buffered
+/// before the list is built. Prefer [`ocaml_list_from_iter`] for double-ended iterators.
+pub fn ocaml_list_from_forward_iter<'a, I, OCamlA>(
+    cr: &'a mut OCamlRuntime,
+    iter: I,
+) -> OCaml<'a, OCamlList<OCamlA>>
+where
+    I: IntoIterator,
+    I::Item: ToOCaml<OCamlA>,
+    OCamlA: 'static,
+{
+    let elts: Vec<I::Item> = iter.into_iter().collect();
+    list_from_rev_iter::<I::Item, OCamlA, _>(cr, elts.into_iter().rev())
+}
+
+/// Like [`ocaml_list_from_iter`], with [`ConversionOptions`].
+pub fn ocaml_list_from_iter_with_options<'a, I, OCamlA>(
+    cr: &'a mut OCamlRuntime,
+    iter: I,
+    options: ConversionOptions,
+) -> OCaml<'a, OCamlList<OCamlA>>
+where
+    I: IntoIterator,
+    I::IntoIter: DoubleEndedIterator,
+    I::Item: ToOCaml<OCamlA>,
+    OCamlA: 'static,
+{
+    list_from_rev_iter_with_options::<I::Item, OCamlA, _>(cr, iter.into_iter().rev(), options)
+}
+
+/// Like [`ocaml_list_from_forward_iter`], with [`ConversionOptions`].
+pub fn ocaml_list_from_forward_iter_with_options<'a, I, OCamlA>(
+    cr: &'a mut OCamlRuntime,
+    iter: I,
+    options: ConversionOptions,
+) -> OCaml<'a, OCamlList<OCamlA>>
+where
+    I: IntoIterator,
+    I::Item: ToOCaml<OCamlA>,
+    OCamlA: 'static,
+{
+    let elts: Vec<I::Item> = iter.into_iter().collect();
+    list_from_rev_iter_with_options::<I::Item, OCamlA, _>(cr, elts.into_iter().rev(), options)
+}
+
+/// Folds the elements of an iterator into an OCaml value by calling an OCaml closure
+/// on each of them, without building an OCaml list first.
+///
+/// Each element is converted to OCaml just before `step` is called with the current
+/// accumulator and the element, so only one element at a time is held in the OCaml heap.
+/// The accumulator lives in a single [`BoxRoot`] that is updated with the result of each
+/// step, so it stays rooted while the next element is converted and while `step` runs.
+/// `init` is only read once, before the first element is converted.
+///
+/// If `step` raises an exception the fold stops and the exception is returned.
+///
+/// ```
+/// # use ocaml_interop::*;
+/// # fn example(cr: &mut OCamlRuntime, add: &BoxRoot<fn(OCamlInt, OCamlInt) -> OCamlInt>) {
+/// let zero: BoxRoot<OCamlInt> = 0i64.to_boxroot(cr);
+/// let sum: i64 = fold_into_ocaml(cr, 1..=10i64, &zero, add)
+///     .expect("the closure raised an exception")
+///     .to_rust();
+/// # }
+/// ```
+pub fn fold_into_ocaml<'a, I, OCamlA, Acc>(
+    cr: &'a mut OCamlRuntime,
+    iter: I,
+    init: OCamlRef<Acc>,
+    step: &BoxRoot<fn(Acc, OCamlA) -> Acc>,
+) -> Result<OCaml<'a, Acc>, OCamlException>
+where
+    I: IntoIterator,
+    I::Item: ToOCaml<OCamlA>,
+    OCamlA: 'static,
+    Acc: 'static,
+{
+    let mut acc: BoxRoot<Acc> = BoxRoot::new(cr.get(init));
+    for elt in iter {
+        let elt: BoxRoot<OCamlA> = elt.to_boxroot(cr);
+        let acc_ref: OCamlRef<Acc> = &acc;
+        let elt_ref: OCamlRef<OCamlA> = &elt;
+        let result = step.try_call::<_, _, (), ()>(cr, &acc_ref, &elt_ref)?;
+        acc.keep(result);
+    }
+    Ok(cr.get(&acc))
+}
+
+/// Formats a value with its `Display` implementation into an OCaml string.
+///
+/// The value is formatted into a buffer that is reused by later calls on the same
+/// thread, so no intermediate `String` is allocated for each conversion.
+///
+/// ```
+/// # use ocaml_interop::*;
+/// # fn example(cr: &mut OCamlRuntime) {
+/// let message: OCaml<String> = display_to_ocaml(cr, &std::net::Ipv4Addr::LOCALHOST);
+/// # }
+/// ```
+pub fn display_to_ocaml<'a, T>(cr: &'a mut OCamlRuntime, value: &T) -> OCaml<'a, String>
+where
+    T: Display + ?Sized,
+{
+    thread_local! {
+        static BUFFER: RefCell<String> = const { RefCell::new(String::new()) };
+    }
+
+    BUFFER.with(move |buffer| {
+        let mut buffer = buffer.borrow_mut();
+        buffer.clear();
+        write!(buffer, "{}", value).expect("a Display implementation returned an error");
+        alloc_string(cr, &buffer)
+    })
+}
+
+/// Builds an OCaml list from the keys of a map.
+///
+/// The order of the elements in the resulting list follows the iteration order of the map
+/// and is not deterministic.
+pub fn ocaml_list_of_keys<'a, K, V, OCamlK>(
+    cr: &'a mut OCamlRuntime,
+    map: &HashMap<K, V, impl BuildHasher>,
+) -> OCaml<'a, OCamlList<OCamlK>>
+where
+    K: ToOCaml<OCamlK>,
+    OCamlK: 'static,
+{
+    list_from_rev_iter::<K, OCamlK, _>(cr, map.keys())
+}
+
+/// Builds an OCaml list from the values of a map.
+///
+/// The order of the elements in the resulting list follows the iteration order of the map
+/// and is not deterministic.
+pub fn ocaml_list_of_values<'a, K, V, OCamlV>(
+    cr: &'a mut OCamlRuntime,
+    map: &HashMap<K, V, impl BuildHasher>,
+) -> OCaml<'a, OCamlList<OCamlV>>
+where
+    V: ToOCaml<OCamlV>,
+    OCamlV: 'static,
+{
+    list_from_rev_iter::<V, OCamlV, _>(cr, map.values())
+}
+
+/// Builds an OCaml list by appending elements at its end.
+///
+/// Unlike [`ocaml_list_from_forward_iter`], the elements don't need to be buffered before
+/// the list is built, each one is converted and linked into the list when it is pushed.
+/// One cons cell is allocated per element.
+///
+/// The partially built list is kept rooted by the builder: its first cell, its last cell
+/// and the cell being filled are rooted, whatever the length of the list. Cells are only
+/// linked into the list once their element has been stored, so the list reachable from
+/// the roots is always complete.
+///
+/// ```
+/// # use ocaml_interop::*;
+/// # fn example(cr: &mut OCamlRuntime) {
+/// let mut builder = ListBuilder::new();
+/// for n in 1..=10i64 {
+///     builder.push(cr, n * n);
+/// }
+/// let squares: BoxRoot<OCamlList<OCamlInt>> = builder.finish();
+/// # }
+/// ```
+pub struct ListBuilder<OCamlA: 'static> {
+    head: BoxRoot<OCamlList<OCamlA>>,
+    last: BoxRoot<OCamlList<OCamlA>>,
+    cell: BoxRoot<OCamlList<OCamlA>>,
+    len: usize,
+}
+
+impl<OCamlA: 'static> ListBuilder<OCamlA> {
+    /// Creates a builder for an empty list.
+    pub fn new() -> Self {
+        ListBuilder {
+            head: BoxRoot::new(OCaml::nil()),
+            last: BoxRoot::new(OCaml::nil()),
+            cell: BoxRoot::new(OCaml::nil()),
+            len: 0,
+        }
+    }
+
+    /// Returns the number of elements pushed so far.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true when no element has been pushed yet.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Converts `elt` and appends it at the end of the list.
+    pub fn push<A: ToOCaml<OCamlA>>(&mut self, cr: &mut OCamlRuntime, elt: A) {
+        let cons = unsafe {
+            let nil = self.cell.keep(OCaml::nil());
+            alloc_cons_placeholder(cr, nil)
+        };
+        self.cell.keep(cons);
+        unsafe {
+            let ov = elt.to_ocaml(cr).get_raw();
+            store_raw_field_at(cr, &self.cell, 0, ov);
+            let cell = cr.get(&self.cell).get_raw();
+            if self.len == 0 {
+                self.head.keep(OCaml::new(cr, cell));
+            } else {
+                store_raw_field_at(cr, &self.last, 1, cell);
+            }
+            self.last.keep(OCaml::new(cr, cell));
+        }
+        self.len += 1;
+    }
+
+    /// Returns the built list.
+    pub fn finish(self) -> BoxRoot<OCamlList<OCamlA>> {
+        self.head
+    }
+}
+
+impl<OCamlA: 'static> Default for ListBuilder<OCamlA> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Converts a vector into an OCaml list.
+///
+/// Any element type that can be converted is supported, for example a `Vec<String>`
+/// (or a `&[String]`, or a `&[&str]`) is converted into an `OCamlList<String>`.
+///
+/// Nested vectors, like `Vec<Vec<A>>`, are converted into lists of lists. Each inner list
+/// is stored in the outer list as soon as it has been built.
+unsafe impl<A, OCamlA: 'static> ToOCaml<OCamlList<OCamlA>> for Vec<A>
+where
+    A: ToOCaml<OCamlA>,
+{
+    fn to_ocaml<'a>(&self, cr: &'a mut OCamlRuntime) -> OCaml<'a, OCamlList<OCamlA>> {
+        self.as_slice().to_ocaml(cr)
+    }
+}
+
+unsafe impl<A, OCamlA: 'static> ToOCaml<OCamlList<OCamlA>> for &Vec<A>
+where
+    A: ToOCaml<OCamlA>,
+{
+    fn to_ocaml<'a>(&self, cr: &'a mut OCamlRuntime) -> OCaml<'a, OCamlList<OCamlA>> {
+        self.as_slice().to_ocaml(cr)
+    }
+}
+
+/// Converts a queue into an OCaml list, the head of the list being the front of the queue.
+unsafe impl<A, OCamlA: 'static> ToOCaml<OCamlList<OCamlA>> for VecDeque<A>
+where
+    A: ToOCaml<OCamlA>,
+{
+    fn to_ocaml<'a>(&self, cr: &'a mut OCamlRuntime) -> OCaml<'a, OCamlList<OCamlA>> {
+        list_from_rev_iter::<A, OCamlA, _>(cr, self.iter().rev())
+    }
+}
+
+unsafe impl<A, OCamlA: 'static> ToOCaml<OCamlList<OCamlA>> for &VecDeque<A>
+where
+    A: ToOCaml<OCamlA>,
+{
+    fn to_ocaml<'a>(&self, cr: &'a mut OCamlRuntime) -> OCaml<'a, OCamlList<OCamlA>> {
+        (*self).to_ocaml(cr)
+    }
+}
+
+/// Converts a set into an OCaml list.
+///
+/// The order of the elements in the resulting list is unspecified.
+unsafe impl<A, S, OCamlA: 'static> ToOCaml<OCamlList<OCamlA>> for HashSet<A, S>
+where
+    A: ToOCaml<OCamlA>,
+    S: BuildHasher,
+{
+    fn to_ocaml<'a>(&self, cr: &'a mut OCamlRuntime) -> OCaml<'a, OCamlList<OCamlA>> {
+        list_from_rev_iter::<A, OCamlA, _>(cr, self.iter())
+    }
+}
+
+unsafe impl<A, S, OCamlA: 'static> ToOCaml<OCamlList<OCamlA>> for &HashSet<A, S>
+where
+    A: ToOCaml<OCamlA>,
+    S: BuildHasher,
+{
+    fn to_ocaml<'a>(&self, cr: &'a mut OCamlRuntime) -> OCaml<'a, OCamlList<OCamlA>> {
+        (*self).to_ocaml(cr)
+    }
+}
+
+/// Converts a set into an OCaml list.
+///
+/// Elements in the resulting list are sorted in ascending order.
+unsafe impl<A, OCamlA: 'static> ToOCaml<OCamlList<OCamlA>> for BTreeSet<A>
+where
+    A: ToOCaml<OCamlA>,
+{
+    fn to_ocaml<'a>(&self, cr: &'a mut OCamlRuntime) -> OCaml<'a, OCamlList<OCamlA>> {
+        list_from_rev_iter::<A, OCamlA, _>(cr, self.iter().rev())
+    }
+}
+
+unsafe impl<A, OCamlA: 'static> ToOCaml<OCamlList<OCamlA>> for &BTreeSet<A>
+where
+    A: ToOCaml<OCamlA>,
+{
+    fn to_ocaml<'a>(&self, cr: &'a mut OCamlRuntime) -> OCaml<'a, OCamlList<OCamlA>> {
+        (*self).to_ocaml(cr)
+    }
+}
+
+unsafe impl<A, OCamlA: 'static, const N: usize> ToOCaml<OCamlList<OCamlA>> for [A; N]
+where
+    A: ToOCaml<OCamlA>,
+{
+    fn to_ocaml<'a>(&self, cr: &'a mut OCamlRuntime) -> OCaml<'a, OCamlList<OCamlA>> {
+        self.as_slice().to_ocaml(cr)
+    }
+}
+
+unsafe impl<A, OCamlA: 'static, const N: usize> ToOCaml<OCamlList<OCamlA>> for &[A; N]
+where
+    A: ToOCaml<OCamlA>,
+{
+    fn to_ocaml<'a>(&self, cr: &'a mut OCamlRuntime) -> OCaml<'a, OCamlList<OCamlA>> {
+        self.as_slice().to_ocaml(cr)
+    }
+}
+
+/// Converts a map into an OCaml association list.
+///
+/// The order of the elements in the resulting list follows the iteration order of the map
+/// and is not deterministic. Maps with any hasher can be converted, the hasher is not
+/// used during the conversion.
+unsafe impl<K, V, S, OCamlK: 'static, OCamlV: 'static> ToOCaml<OCamlList<(OCamlK, OCamlV)>>
+    for HashMap<K, V, S>
+where
+    K: ToOCaml<OCamlK>,
+    V: ToOCaml<OCamlV>,
+    S: BuildHasher,
+{
+    fn to_ocaml<'a>(&self, cr: &'a mut OCamlRuntime) -> OCaml<'a, OCamlList<(OCamlK, OCamlV)>> {
+        let mut result = BoxRoot::new(OCaml::nil());
+        for (key, value) in self.iter() {
+            let ov = pair_to_ocaml(cr, key, value).root();
+            let cons = alloc_cons(cr, &ov, &result);
+            result.keep(cons);
+        }
+        cr.get(&result)
+    }
+}
+
+unsafe impl<K, V, S, OCamlK: 'static, OCamlV: 'static> ToOCaml<OCamlList<(OCamlK, OCamlV)>>
+    for &HashMap<K, V, S>
+where
+    K: ToOCaml<OCamlK>,
+    V: ToOCaml<OCamlV>,
+    S: BuildHasher,
+{
+    fn to_ocaml<'a>(&self, cr: &'a mut OCamlRuntime) -> OCaml<'a, OCamlList<(OCamlK, OCamlV)>> {
+        (*self).to_ocaml(cr)
+    }
+}
+
+/// Converts a map into an OCaml association list.
+///
+/// Elements in the resulting list are sorted in ascending key order.
+unsafe impl<K, V, OCamlK: 'static, OCamlV: 'static> ToOCaml<OCamlList<(OCamlK, OCamlV)>>
+    for BTreeMap<K, V>
+where
+    K: ToOCaml<OCamlK>,
+    V: ToOCaml<OCamlV>,
+{
+    fn to_ocaml<'a>(&self, cr: &'a mut OCamlRuntime) -> OCaml<'a, OCamlList<(OCamlK, OCamlV)>> {
+        let mut result = BoxRoot::new(OCaml::nil());
+        for (key, value) in self.iter().rev() {
+            let ov = pair_to_ocaml(cr, key, value).root();
+            let cons = alloc_cons(cr, &ov, &result);
+            result.keep(cons);
+        }
+        cr.get(&result)
+    }
+}
+
+unsafe impl<K, V, OCamlK: 'static, OCamlV: 'static> ToOCaml<OCamlList<(OCamlK, OCamlV)>>
+    for &BTreeMap<K, V>
+where
+    K: ToOCaml<OCamlK>,
+    V: ToOCaml<OCamlV>,
+{
+    fn to_ocaml<'a>(&self, cr: &'a mut OCamlRuntime) -> OCaml<'a, OCamlList<(OCamlK, OCamlV)>> {
+        (*self).to_ocaml(cr)
+    }
+}
+
+// Ranges
+
+/// Converts a range into an OCaml pair of its bounds, `(start, end)`.
+///
+/// The range is half-open: `end` is not included in the range.
+unsafe impl<A, OCamlA: 'static> ToOCaml<(OCamlA, OCamlA)> for Range<A>
+where
+    A: ToOCaml<OCamlA>,
+{
+    fn to_ocaml<'a>(&self, cr: &'a mut OCamlRuntime) -> OCaml<'a, (OCamlA, OCamlA)> {
+        pair_to_ocaml(cr, &self.start, &self.end)
+    }
+}
+
+/// Converts an inclusive range into an OCaml pair of its bounds, `(start, end)`.
+///
+/// Unlike with [`Range`], `end` is included in the range.
+unsafe impl<A, OCamlA: 'static> ToOCaml<(OCamlA, OCamlA)> for RangeInclusive<A>
+where
+    A: ToOCaml<OCamlA>,
+{
+    fn to_ocaml<'a>(&self, cr: &'a mut OCamlRuntime) -> OCaml<'a, (OCamlA, OCamlA)> {
+        pair_to_ocaml(cr, self.start(), self.end())
+    }
+}
+
+// Tuples
+
+// Same as the 2-tuples conversion, but from borrowed components, like
+// the key-value pairs obtained when iterating maps.
+fn pair_to_ocaml<'a, A, OCamlA: 'static, B, OCamlB: 'static>(
+    cr: &'a mut OCamlRuntime,
+    fst: &A,
+    snd: &B,
+) -> OCaml<'a, (OCamlA, OCamlB)>
+where
+    A: ToOCaml<OCamlA>,
+    B: ToOCaml<OCamlB>,
+{
+    let ocaml_tuple: BoxRoot<(OCamlA, OCamlB)> = BoxRoot::new(unsafe { alloc_tuple(cr, 2) });
+    unsafe {
+        let field_val = fst.to_ocaml(cr).get_raw();
+        store_raw_field_at(cr, &ocaml_tuple, 0, field_val);
+        let field_val = snd.to_ocaml(cr).get_raw();
+        store_raw_field_at(cr, &ocaml_tuple, 1, field_val);
+    }
+    cr.get(&ocaml_tuple)
+}
+
+// The fields are stored through the write barrier: converting a field can allocate, and
+// move the tuple to the major heap before the next field is stored.
+macro_rules! tuple_to_ocaml {
+    ($($n:tt: $t:ident => $ot:ident),+) => {
+        /// Converts a tuple into an OCaml tuple with the same number of fields.
+        ///
+        /// Like in OCaml, unit elements still occupy a field of the tuple, holding `()`: a
+        /// `((), i64)` converts into a `((), OCamlInt)`, an OCaml `unit * int`.
+        unsafe impl<$($t),+, $($ot: 'static),+> ToOCaml<($($ot),+)> for ($($t),+)
         where
             $($t: ToOCaml<$ot>),+
         {
@@ -296,6 +1580,155 @@ tuple_to_ocaml!(
     7: H => OCamlH,
     8: I => OCamlI,
     9: J => OCamlJ);
+tuple_to_ocaml!(
+    0: A => OCamlA,
+    1: B => OCamlB,
+    2: C => OCamlC,
+    3: D => OCamlD,
+    4: E => OCamlE,
+    5: F => OCamlF,
+    6: G => OCamlG,
+    7: H => OCamlH,
+    8: I => OCamlI,
+    9: J => OCamlJ,
+    10: K => OCamlK);
+tuple_to_ocaml!(
+    0: A => OCamlA,
+    1: B => OCamlB,
+    2: C => OCamlC,
+    3: D => OCamlD,
+    4: E => OCamlE,
+    5: F => OCamlF,
+    6: G => OCamlG,
+    7: H => OCamlH,
+    8: I => OCamlI,
+    9: J => OCamlJ,
+    10: K => OCamlK,
+    11: L => OCamlL);
+tuple_to_ocaml!(
+    0: A => OCamlA,
+    1: B => OCamlB,
+    2: C => OCamlC,
+    3: D => OCamlD,
+    4: E => OCamlE,
+    5: F => OCamlF,
+    6: G => OCamlG,
+    7: H => OCamlH,
+    8: I => OCamlI,
+    9: J => OCamlJ,
+    10: K => OCamlK,
+    11: L => OCamlL,
+    12: M => OCamlM);
+tuple_to_ocaml!(
+    0: A => OCamlA,
+    1: B => OCamlB,
+    2: C => OCamlC,
+    3: D => OCamlD,
+    4: E => OCamlE,
+    5: F => OCamlF,
+    6: G => OCamlG,
+    7: H => OCamlH,
+    8: I => OCamlI,
+    9: J => OCamlJ,
+    10: K => OCamlK,
+    11: L => OCamlL,
+    12: M => OCamlM,
+    13: N => OCamlN);
+tuple_to_ocaml!(
+    0: A => OCamlA,
+    1: B => OCamlB,
+    2: C => OCamlC,
+    3: D => OCamlD,
+    4: E => OCamlE,
+    5: F => OCamlF,
+    6: G => OCamlG,
+    7: H => OCamlH,
+    8: I => OCamlI,
+    9: J => OCamlJ,
+    10: K => OCamlK,
+    11: L => OCamlL,
+    12: M => OCamlM,
+    13: N => OCamlN,
+    14: O => OCamlO);
+tuple_to_ocaml!(
+    0: A => OCamlA,
+    1: B => OCamlB,
+    2: C => OCamlC,
+    3: D => OCamlD,
+    4: E => OCamlE,
+    5: F => OCamlF,
+    6: G => OCamlG,
+    7: H => OCamlH,
+    8: I => OCamlI,
+    9: J => OCamlJ,
+    10: K => OCamlK,
+    11: L => OCamlL,
+    12: M => OCamlM,
+    13: N => OCamlN,
+    14: O => OCamlO,
+    15: P => OCamlP);
+
+// Converts the `len` items of `iter` and stores them in a new tuple of that length.
+fn tuple_from_iter<'a, A, OCamlA, T: 'static>(
+    cr: &'a mut OCamlRuntime,
+    iter: impl IntoIterator<Item = A>,
+    len: usize,
+) -> OCaml<'a, T>
+where
+    A: ToOCaml<OCamlA>,
+{
+    let mut iter = iter.into_iter();
+    let ocaml_tuple: BoxRoot<T> = BoxRoot::new(unsafe { alloc_tuple(cr, len) });
+    for index in 0..len {
+        let item = match iter.next() {
+            Some(item) => item,
+            None => panic!(
+                "expected an iterator of {} items to build a tuple, got {} items",
+                len, index
+            ),
+        };
+        unsafe {
+            let field_val = item.to_ocaml(cr).get_raw();
+            store_raw_field_at(cr, &ocaml_tuple, index, field_val);
+        }
+    }
+    assert!(
+        iter.next().is_none(),
+        "expected an iterator of {} items to build a tuple, got more items",
+        len
+    );
+    cr.get(&ocaml_tuple)
+}
+
+macro_rules! iter_to_tuple {
+    ($($name:ident => $len:literal ($($ot:ident),+);)+) => {
+        $(
+            #[doc = concat!(
+                "Converts the items of an iterator that yields exactly ", $len, " items into an OCaml tuple.\n\n",
+                "This is handy to convert rows assembled from several columns, ",
+                "like `columns.iter().map(|column| &column[row])`.\n\n",
+                "# Panics\n\n",
+                "Panics if the iterator doesn't yield exactly ", $len, " items."
+            )]
+            pub fn $name<'a, A, OCamlA: 'static>(
+                cr: &'a mut OCamlRuntime,
+                iter: impl IntoIterator<Item = A>,
+            ) -> OCaml<'a, ($($ot),+)>
+            where
+                A: ToOCaml<OCamlA>,
+            {
+                tuple_from_iter::<A, OCamlA, _>(cr, iter, $len)
+            }
+        )+
+    };
+}
+
+iter_to_tuple! {
+    iter_to_tuple2 => 2 (OCamlA, OCamlA);
+    iter_to_tuple3 => 3 (OCamlA, OCamlA, OCamlA);
+    iter_to_tuple4 => 4 (OCamlA, OCamlA, OCamlA, OCamlA);
+    iter_to_tuple5 => 5 (OCamlA, OCamlA, OCamlA, OCamlA, OCamlA);
+}
 
 // This copies
 unsafe impl<A: BigarrayElt> ToOCaml<Array1<A>> for &[A] {
@@ -304,6 +1737,13 @@ unsafe impl<A: BigarrayElt> ToOCaml<Array1<A>> for &[A] {
     }
 }
 
+// This copies too, use `alloc_bigarray1_from_vec` to move the buffer instead
+unsafe impl<A: BigarrayElt> ToOCaml<Array1<A>> for Vec<A> {
+    fn to_ocaml<'a>(&self, cr: &'a mut OCamlRuntime) -> OCaml<'a, Array1<A>> {
+        alloc_bigarray1(cr, self)
+    }
+}
+
 // Note: we deliberately don't implement FromOCaml<Array1<A>>,
 // because this trait doesn't have a lifetime parameter
 // and implementing would force a copy.
@@ -315,3 +1755,95 @@ impl<'a, A: BigarrayElt> Borrow<[A]> for OCaml<'a, Array1<A>> {
         }
     }
 }
+
+// This copies
+unsafe impl ToOCaml<OCamlFloatArray> for &[f64] {
+    fn to_ocaml<'a>(&self, cr: &'a mut OCamlRuntime) -> OCaml<'a, OCamlFloatArray> {
+        alloc_float_array(cr, self)
+    }
+}
+
+unsafe impl<const N: usize> ToOCaml<OCamlFloatArray> for [f64; N] {
+    fn to_ocaml<'a>(&self, cr: &'a mut OCamlRuntime) -> OCaml<'a, OCamlFloatArray> {
+        alloc_float_array(cr, self)
+    }
+}
+
+unsafe impl<const N: usize> ToOCaml<OCamlFloatArray> for &[f64; N] {
+    fn to_ocaml<'a>(&self, cr: &'a mut OCamlRuntime) -> OCaml<'a, OCamlFloatArray> {
+        alloc_float_array(cr, *self)
+    }
+}
+
+/// Converts a slice into an OCaml `array`, in the same order.
+///
+/// Unlike lists, OCaml arrays can be indexed in constant time. Each element is converted
+/// and stored in the array in turn, only the array being built is rooted.
+///
+/// Only OCaml types that implement [`OCamlArrayElement`] can be the elements, which
+/// leaves out `float`: OCaml expects float arrays to hold unboxed floats, convert a
+/// `&[f64]` into an [`OCamlFloatArray`] instead.
+unsafe impl<A, OCamlA: OCamlArrayElement> ToOCaml<OCamlArray<OCamlA>> for &[A]
+where
+    A: ToOCaml<OCamlA>,
+{
+    fn to_ocaml<'a>(&self, cr: &'a mut OCamlRuntime) -> OCaml<'a, OCamlArray<OCamlA>> {
+        let array = BoxRoot::new(unsafe { alloc_array(cr, self.len()) });
+        for (index, elt) in self.iter().enumerate() {
+            unsafe {
+                let ov = elt.to_ocaml(cr).get_raw();
+                store_raw_field_at(cr, &array, index, ov);
+            }
+        }
+        cr.get(&array)
+    }
+}
+
+unsafe impl<A, OCamlA: OCamlArrayElement> ToOCaml<OCamlArray<OCamlA>> for Vec<A>
+where
+    A: ToOCaml<OCamlA>,
+{
+    fn to_ocaml<'a>(&self, cr: &'a mut OCamlRuntime) -> OCaml<'a, OCamlArray<OCamlA>> {
+        self.as_slice().to_ocaml(cr)
+    }
+}
+
+unsafe impl<A, OCamlA: OCamlArrayElement> ToOCaml<OCamlArray<OCamlA>> for &Vec<A>
+where
+    A: ToOCaml<OCamlA>,
+{
+    fn to_ocaml<'a>(&self, cr: &'a mut OCamlRuntime) -> OCaml<'a, OCamlArray<OCamlA>> {
+        self.as_slice().to_ocaml(cr)
+    }
+}
+
+impl<'a> Borrow<[f64]> for OCaml<'a, OCamlFloatArray> {
+    fn borrow(&self) -> &[f64] {
+        unsafe {
+            let wosize = wosize_val(self.raw());
+            if wosize == 0 {
+                return &[];
+            }
+            assert!(
+                tag_val(self.raw()) == tag::DOUBLE_ARRAY,
+                "attempt to perform a float array operation on an OCaml value that is not a float array"
+            );
+            let len = wosize * core::mem::size_of::<RawOCaml>() / core::mem::size_of::<f64>();
+            core::slice::from_raw_parts(self.raw() as *const f64, len)
+        }
+    }
+}
+
+// This copies
+unsafe impl<'b, A: BigarrayElt> ToOCaml<Array2<A>> for Matrix<'b, A> {
+    fn to_ocaml<'a>(&self, cr: &'a mut OCamlRuntime) -> OCaml<'a, Array2<A>> {
+        alloc_bigarray2(cr, self.rows(), self.cols(), self.data())
+    }
+}
+
+// Flat, row-major view of the data
+impl<'a, A: BigarrayElt> Borrow<[A]> for OCaml<'a, Array2<A>> {
+    fn borrow(&self) -> &[A] {
+        self.as_slice()
+    }
+}
@@ -0,0 +1,129 @@
+// Copyright (c) Viable Systems and TezEdge Contributors
+// SPDX-License-Identifier: MIT
+
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::mpsc::{channel, Receiver, Sender, TryRecvError};
+
+use crate::OCamlRuntime;
+
+type Job = Box<dyn FnOnce(&mut OCamlRuntime) + Send>;
+
+/// Queue of calls into OCaml, submitted from other threads and run on the thread
+/// that owns the OCaml runtime.
+///
+/// Only the thread that owns the [`OCamlRuntime`] handle can convert values and call
+/// OCaml functions. Other threads get an [`OCamlDispatchHandle`] from the dispatcher
+/// and use it to send closures that get the runtime handle as argument, and the thread
+/// that owns the runtime runs them with [`OCamlDispatcher::run_pending`] or
+/// [`OCamlDispatcher::run_one`].
+///
+/// This does not make the OCaml runtime multi-threaded, the closures run one after the
+/// other, on the thread that owns the runtime, only when that thread asks for it. The
+/// dispatcher just moves the work to the right thread, and the results back.
+///
+/// ```
+/// # use ocaml_interop::*;
+/// # ocaml! { fn twice(num: OCamlInt) -> OCamlInt; }
+/// # fn example(cr: &mut OCamlRuntime) {
+/// let dispatcher = OCamlDispatcher::new();
+/// let handle = dispatcher.handle();
+/// let worker = std::thread::spawn(move || {
+///     let result = handle.dispatch(|cr| {
+///         let num = 21i64.to_boxroot(cr);
+///         twice(cr, &num).to_rust::<i64>(cr)
+///     });
+///     result.recv().unwrap()
+/// });
+/// dispatcher.run_one(cr);
+/// assert_eq!(worker.join().unwrap(), 42);
+/// # }
+/// ```
+pub struct OCamlDispatcher {
+    sender: Sender<Job>,
+    receiver: Receiver<Job>,
+}
+
+/// Handle used to submit calls to an [`OCamlDispatcher`] from any thread.
+#[derive(Clone)]
+pub struct OCamlDispatchHandle {
+    sender: Sender<Job>,
+}
+
+impl OCamlDispatcher {
+    /// Creates a dispatcher with an empty queue.
+    pub fn new() -> Self {
+        let (sender, receiver) = channel();
+        OCamlDispatcher { sender, receiver }
+    }
+
+    /// Returns a handle that submits calls to this dispatcher.
+    pub fn handle(&self) -> OCamlDispatchHandle {
+        OCamlDispatchHandle {
+            sender: self.sender.clone(),
+        }
+    }
+
+    /// Runs the calls that are already queued, without waiting for more.
+    ///
+    /// Returns the number of calls that were run. Calls that panic are counted too, their
+    /// panics are not propagated (see [`OCamlDispatchHandle::dispatch`]).
+    pub fn run_pending(&self, cr: &mut OCamlRuntime) -> usize {
+        let mut count = 0;
+        loop {
+            match self.receiver.try_recv() {
+                Ok(job) => {
+                    job(cr);
+                    count += 1;
+                }
+                Err(TryRecvError::Empty) | Err(TryRecvError::Disconnected) => return count,
+            }
+        }
+    }
+
+    /// Waits until a call is queued and runs it.
+    ///
+    /// If the call panics the panic is not propagated (see [`OCamlDispatchHandle::dispatch`]).
+    pub fn run_one(&self, cr: &mut OCamlRuntime) {
+        // The dispatcher holds a sender itself, so the channel can't be disconnected
+        // while it is being waited on.
+        let job = self
+            .receiver
+            .recv()
+            .expect("the dispatcher channel was disconnected");
+        job(cr);
+    }
+}
+
+impl Default for OCamlDispatcher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl OCamlDispatchHandle {
+    /// Queues `f` to be run on the thread that owns the OCaml runtime.
+    ///
+    /// Returns a channel that receives the result of `f` once it has run. If `f` panics,
+    /// or the dispatcher is dropped before running it, the channel is disconnected
+    /// without receiving anything. The panic is caught on the thread that owns the
+    /// runtime, it doesn't unwind out of [`OCamlDispatcher::run_pending`] or
+    /// [`OCamlDispatcher::run_one`], and the dispatcher keeps running the calls that
+    /// follow.
+    pub fn dispatch<T, F>(&self, f: F) -> Receiver<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut OCamlRuntime) -> T + Send + 'static,
+    {
+        let (result_sender, result_receiver) = channel();
+        let job: Job = Box::new(move |cr| {
+            // On panic the sender is dropped, which disconnects the channel
+            if let Ok(result) = panic::catch_unwind(AssertUnwindSafe(|| f(cr))) {
+                // The caller may have stopped waiting for the result
+                let _ = result_sender.send(result);
+            }
+        });
+        // If the dispatcher is gone the job is dropped, which disconnects the channel
+        let _ = self.sender.send(job);
+        result_receiver
+    }
+}
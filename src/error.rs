@@ -4,18 +4,32 @@
 use crate::mlvalues::{is_block, string_val, tag_val, RawOCaml};
 use crate::mlvalues::{tag, MAX_FIXNUM, MIN_FIXNUM};
 use core::{fmt, slice};
-use ocaml_sys::caml_string_length;
+use ocaml_sys::{caml_format_exception, caml_string_length, field};
+use std::ffi::{c_void, CStr, OsString};
+
+extern "C" {
+    fn caml_stat_free(ptr: *mut c_void);
+}
 
 /// An OCaml exception value.
+///
+/// The name of the exception constructor and its printed form are captured when the
+/// exception is obtained, and the [`Display`](fmt::Display) implementation shows the
+/// printed form. It is the default formatting of the OCaml runtime (the one used for
+/// uncaught exceptions): printers registered with `Printexc.register_printer` are not
+/// used, so it can differ from what `Printexc.to_string` returns.
 #[derive(Debug)]
 pub struct OCamlException {
     raw: RawOCaml,
+    name: String,
+    printed: String,
 }
 
 #[derive(Debug)]
 pub enum OCamlFixnumConversionError {
     InputTooBig(i64),
     InputTooSmall(i64),
+    UnsignedInputTooBig(u64),
 }
 
 impl fmt::Display for OCamlFixnumConversionError {
@@ -31,6 +45,58 @@ impl fmt::Display for OCamlFixnumConversionError {
                 "Input value doesn't fit in OCaml fixnum n={} < MIN_FIXNUM={}",
                 n, MIN_FIXNUM
             ),
+            OCamlFixnumConversionError::UnsignedInputTooBig(n) => write!(
+                f,
+                "Input value doesn't fit in OCaml fixnum n={} > MAX_FIXNUM={}",
+                n, MAX_FIXNUM
+            ),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum OCamlCharConversionError {
+    InputOutOfRange(char),
+}
+
+impl fmt::Display for OCamlCharConversionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            OCamlCharConversionError::InputOutOfRange(c) => write!(
+                f,
+                "Input value doesn't fit in OCaml char c={:?} (U+{:04X}) > U+00FF",
+                c, *c as u32
+            ),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum OCamlFloatConversionError {
+    NotFinite(f64),
+}
+
+impl fmt::Display for OCamlFloatConversionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            OCamlFloatConversionError::NotFinite(x) => {
+                write!(f, "Input value is not a finite number x={}", x)
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum OCamlStringConversionError {
+    InvalidUnicode(OsString),
+}
+
+impl fmt::Display for OCamlStringConversionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            OCamlStringConversionError::InvalidUnicode(s) => {
+                write!(f, "Input value is not valid Unicode s={:?}", s)
+            }
         }
     }
 }
@@ -38,7 +104,22 @@ impl fmt::Display for OCamlFixnumConversionError {
 impl OCamlException {
     #[doc(hidden)]
     pub unsafe fn of(raw: RawOCaml) -> Self {
-        OCamlException { raw }
+        let printed = caml_format_exception(raw);
+        let printed_str = CStr::from_ptr(printed).to_string_lossy().into_owned();
+        caml_stat_free(printed as *mut c_void);
+        OCamlException {
+            raw,
+            name: exception_name(raw).unwrap_or_else(|| printed_str.clone()),
+            printed: printed_str,
+        }
+    }
+
+    /// Returns the name of the exception constructor, like `Failure` or `Not_found`.
+    ///
+    /// Exceptions declared in OCaml modules are qualified with the module path, like
+    /// `Callable.WithInt`.
+    pub fn name(&self) -> &str {
+        &self.name
     }
 
     pub fn message(&self) -> Option<String> {
@@ -61,3 +142,32 @@ impl OCamlException {
         }
     }
 }
+
+impl fmt::Display for OCamlException {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.printed)
+    }
+}
+
+// Exceptions without arguments are the constructor block itself, exceptions with
+// arguments are blocks with the constructor as first field. Constructors are
+// `Object_tag` blocks with the name as first field.
+unsafe fn exception_name(raw: RawOCaml) -> Option<String> {
+    if !is_block(raw) {
+        return None;
+    }
+    let constructor = if tag_val(raw) == tag::OBJECT {
+        raw
+    } else {
+        *field(raw, 0)
+    };
+    if !is_block(constructor) || tag_val(constructor) != tag::OBJECT {
+        return None;
+    }
+    let name = *field(constructor, 0);
+    if !is_block(name) || tag_val(name) != tag::STRING {
+        return None;
+    }
+    let bytes = slice::from_raw_parts(string_val(name), caml_string_length(name));
+    Some(String::from_utf8_lossy(bytes).into_owned())
+}
@@ -285,6 +285,7 @@
 mod boxroot;
 mod closure;
 mod conv;
+mod dispatcher;
 mod error;
 mod macros;
 mod memory;
@@ -292,23 +293,54 @@ mod mlvalues;
 mod runtime;
 mod value;
 
-pub use crate::boxroot::BoxRoot;
+pub use crate::boxroot::{BoxRoot, BoxRootPool, PooledRoot};
 
 pub use crate::closure::{OCamlFn1, OCamlFn2, OCamlFn3, OCamlFn4, OCamlFn5};
-pub use crate::conv::{FromOCaml, ToOCaml};
-pub use crate::error::OCamlException;
+#[cfg(feature = "serde_json")]
+pub use crate::conv::OCamlJson;
+pub use crate::conv::{
+    display_to_ocaml, flatten_to_ocaml, fold_into_ocaml, instant_to_ocaml, iter_to_tuple2,
+    iter_to_tuple3, iter_to_tuple4, iter_to_tuple5, ocaml_list_from_forward_iter,
+    ocaml_list_from_forward_iter_with_options, ocaml_list_from_iter,
+    ocaml_list_from_iter_with_options, ocaml_list_of_keys, ocaml_list_of_values, ConversionOptions,
+    FromOCaml, ListBuilder, ToOCaml, ToOCamlOrRaise, TryFromOCaml, TryToOCaml,
+};
+#[cfg(feature = "serde")]
+pub use crate::conv::{to_ocaml_via_serde, OCamlSerializeError, OCamlSerializer};
+pub use crate::dispatcher::{OCamlDispatchHandle, OCamlDispatcher};
+pub use crate::error::{
+    OCamlCharConversionError, OCamlException, OCamlFixnumConversionError,
+    OCamlFloatConversionError, OCamlStringConversionError,
+};
+pub use crate::memory::alloc_array;
+pub use crate::memory::alloc_bigarray1_from_vec;
+pub use crate::memory::alloc_bytes_chunked;
 pub use crate::memory::alloc_cons as cons;
+pub use crate::memory::alloc_custom_box;
+pub use crate::memory::alloc_float_record;
+pub use crate::memory::alloc_poly_variant;
+pub use crate::memory::blit_str_into;
+pub use crate::memory::deep_copy;
+pub use crate::memory::raise_failure;
+pub use crate::memory::store_array_element;
 pub use crate::memory::OCamlRef;
+pub use crate::memory::{OCamlStringBuilder, RecordBuilder};
 pub use crate::mlvalues::{
-    bigarray, DynBox, OCamlBytes, OCamlFloat, OCamlInt, OCamlInt32, OCamlInt64, OCamlList, RawOCaml,
+    bigarray, Custom, DynBox, OCamlArray, OCamlArrayElement, OCamlBytes, OCamlChar,
+    OCamlControlFlow, OCamlExn, OCamlFloat, OCamlFloatArray, OCamlInt, OCamlInt32, OCamlInt64,
+    OCamlList, OCamlNativeInt, PolyVariant, RawOCaml, OCAML_INT_BITS,
 };
-pub use crate::runtime::OCamlRuntime;
+pub use crate::runtime::{GcStats, OCamlRuntime};
 pub use crate::value::OCaml;
+pub use ocaml_interop_derive::ToOCaml;
 
 #[doc(hidden)]
 pub mod internal {
     pub use crate::closure::OCamlClosure;
-    pub use crate::memory::{alloc_tuple, caml_alloc, store_field};
+    pub use crate::memory::{
+        alloc_tuple, alloc_variant, caml_alloc, store_field, store_field_no_barrier,
+        store_raw_field_at,
+    };
     pub use crate::mlvalues::tag;
     pub use crate::mlvalues::UNIT;
     pub use ocaml_boxroot_sys::{boxroot_setup, boxroot_teardown};
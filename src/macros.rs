@@ -464,6 +464,70 @@ macro_rules! ocaml_alloc_record {
     };
 }
 
+/// Builds an OCaml record from a list of field values.
+///
+/// Each value is converted into the OCaml type given for its field and stored in the
+/// record through a [`RecordBuilder`](crate::RecordBuilder), in the order in which the
+/// fields are listed. Unlike [`ocaml_alloc_record!`], the values don't need to come from
+/// the fields of a Rust record.
+///
+/// It is important that the order of the fields remains the same as in the OCaml type declaration.
+/// The names of the fields can't be checked against it, they are only there to make the
+/// correspondence easier to follow.
+///
+/// When the OCaml type of every field is written as `OCamlFloat`, the record is built
+/// flat with [`alloc_float_record`](crate::alloc_float_record), as OCaml expects for
+/// records whose fields are all `float`. The type must be spelled exactly `OCamlFloat`
+/// for this to be detected, not through a path or an alias.
+///
+/// # Examples
+///
+/// ```
+/// # use ocaml_interop::*;
+/// // Assuming an OCaml record declaration like:
+/// //
+/// //      type point = {
+/// //          x: int;
+/// //          label: string;
+/// //      }
+/// struct Point;
+///
+/// # fn record_example(cr: &mut OCamlRuntime) {
+/// let point: OCaml<Point> = ocaml_record! {
+///     //  OCamlRecordType { field: OCamlType = value, ... }
+///     cr, Point {  // cr: &mut OCamlRuntime
+///         x: OCamlInt = 10i64,
+///         label: String = "origin",
+///     }
+/// };
+/// # }
+/// ```
+#[macro_export]
+macro_rules! ocaml_record {
+    ($cr:ident, $record_typ:ty {
+        $($field:ident : OCamlFloat = $value:expr),+ $(,)?
+    }) => {{
+        let fields = [$({
+            let value: f64 = $crate::ToOCaml::<$crate::OCamlFloat>::to_ocaml(&$value, $cr).to_rust();
+            value
+        }),+];
+        unsafe { $crate::alloc_float_record::<$record_typ>($cr, &fields) }
+    }};
+
+    ($cr:ident, $record_typ:ty {
+        $($field:ident : $ocaml_typ:ty = $value:expr),+ $(,)?
+    }) => {{
+        let mut builder: $crate::RecordBuilder<$record_typ> =
+            $crate::RecordBuilder::new($cr, $crate::count_fields!($($field)*));
+        let mut current = 0;
+        $(
+            builder.set_field::<$ocaml_typ, _>(current, $value);
+            current += 1;
+        )+
+        builder.build()
+    }};
+}
+
 /// Implements [`FromOCaml`] for mapping an OCaml record into a Rust record.
 ///
 /// It is important that the order of the fields remains the same as in the OCaml type declaration.
@@ -1129,6 +1193,98 @@ macro_rules! ocaml_alloc_polymorphic_variant {
     };
 }
 
+/// Implements [`ToOCaml`]`<`[`OCamlInt`]`>` for flag set types, like the ones generated by
+/// the `bitflags` crate, converting them into their bits.
+///
+/// Any type with a `bits()` method returning an integer can be used. The bits must fit
+/// in an OCaml int, which leaves room for 62 flags (plus the sign bit) on 64-bit
+/// platforms, converting a value that doesn't fit panics.
+///
+/// # Examples
+///
+/// ```
+/// # use ocaml_interop::*;
+/// // With `bitflags`, `bits()` is generated by the `bitflags!` macro
+/// #[derive(Clone, Copy)]
+/// struct Permissions(u32);
+///
+/// impl Permissions {
+///     const READ: Self = Permissions(0b001);
+///     const WRITE: Self = Permissions(0b010);
+///
+///     fn bits(&self) -> u32 {
+///         self.0
+///     }
+/// }
+///
+/// // Assuming OCaml code that tests the bits of an `int`, like:
+/// //
+/// //      let can_write perms = perms land 0b010 <> 0
+///
+/// impl_ocaml_bitflags!(Permissions);
+///
+/// # fn example(cr: &mut OCamlRuntime) {
+/// let perms = Permissions(Permissions::READ.bits() | Permissions::WRITE.bits());
+/// let ocaml_perms: BoxRoot<OCamlInt> = perms.to_boxroot(cr);
+/// # }
+/// ```
+#[macro_export]
+macro_rules! impl_ocaml_bitflags {
+    ($($rust_typ:ty),+ $(,)?) => {
+        $(
+            unsafe impl $crate::ToOCaml<$crate::OCamlInt> for $rust_typ {
+                fn to_ocaml<'a>(
+                    &self,
+                    cr: &'a mut $crate::OCamlRuntime,
+                ) -> $crate::OCaml<'a, $crate::OCamlInt> {
+                    let bits = self.bits();
+                    let ocaml_bits = <i64 as ::core::convert::TryFrom<_>>::try_from(bits)
+                        .ok()
+                        .and_then(|bits| $crate::OCaml::of_i64(bits).ok());
+                    match ocaml_bits {
+                        Some(ocaml_bits) => unsafe { $crate::OCaml::new(cr, ocaml_bits.raw()) },
+                        None => panic!(
+                            "the bits of {} don't fit in an OCaml int: {:#x}",
+                            stringify!($rust_typ),
+                            bits
+                        ),
+                    }
+                }
+            }
+        )+
+    };
+}
+
+/// Builds a right-nested pair `(a, (b, (c, ...)))` from a list of values, or the
+/// corresponding type from a list of types when prefixed with `type`.
+///
+/// Pairs convert to and from OCaml pairs, so nested pairs can represent aggregates wider
+/// than the largest supported tuple, matching an OCaml type like
+/// `int * (string * (bool * float))`.
+///
+/// # Examples
+///
+/// ```
+/// # use ocaml_interop::*;
+/// # fn example(cr: &mut OCamlRuntime) {
+/// let value = nested_tuple!(1i64, "one".to_owned(), true);
+/// let ocaml_value: BoxRoot<nested_tuple!(type OCamlInt, String, bool)> = value.to_boxroot(cr);
+/// let (n, (s, b)): nested_tuple!(type i64, String, bool) = ocaml_value.to_rust(cr);
+/// # }
+/// ```
+#[macro_export]
+macro_rules! nested_tuple {
+    (type $last:ty $(,)?) => { $last };
+    (type $head:ty, $($tail:ty),+ $(,)?) => {
+        ($head, $crate::nested_tuple!(type $($tail),+))
+    };
+
+    ($last:expr $(,)?) => { $last };
+    ($head:expr, $($tail:expr),+ $(,)?) => {
+        ($head, $crate::nested_tuple!($($tail),+))
+    };
+}
+
 // Internal utility macros
 
 #[doc(hidden)]
@@ -1328,8 +1484,8 @@ macro_rules! ocaml_alloc_polymorphic_variant_match {
                         $crate::ToOCaml::to_boxroot($unit_block_slot_name, $cr);
                     unsafe {
                         let block = $crate::internal::caml_alloc(2, $crate::internal::tag::TAG_POLYMORPHIC_VARIANT);
-                        $crate::internal::store_field(block, 0, polytag);
-                        $crate::internal::store_field(block, 1, $unit_block_slot_name.get($cr).raw());
+                        $crate::internal::store_field_no_barrier(block, 0, polytag);
+                        $crate::internal::store_field_no_barrier(block, 1, $unit_block_slot_name.get($cr).raw());
                         $crate::OCaml::new($cr, block)
                     }
                 },
@@ -1351,8 +1507,8 @@ macro_rules! ocaml_alloc_polymorphic_variant_match {
                     )+
                     unsafe {
                         let block = $crate::internal::caml_alloc(2, $crate::internal::tag::TAG_POLYMORPHIC_VARIANT);
-                        $crate::internal::store_field(block, 0, polytag);
-                        $crate::internal::store_field(block, 1, tuple.get($cr).raw());
+                        $crate::internal::store_field_no_barrier(block, 0, polytag);
+                        $crate::internal::store_field_no_barrier(block, 1, tuple.get($cr).raw());
                         $crate::OCaml::new($cr, block)
                     }
                 },
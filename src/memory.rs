@@ -2,30 +2,47 @@
 // SPDX-License-Identifier: MIT
 
 use crate::{
-    conv::FromOCaml,
+    boxroot::BoxRoot,
+    conv::{FromOCaml, ToOCaml, TryFromOCaml},
     mlvalues::{
-        bigarray::{Array1, BigarrayElt},
-        tag, DynBox, OCamlBytes, OCamlFloat, OCamlInt32, OCamlInt64, OCamlList, RawOCaml,
+        bigarray::{Array1, Array2, BigarrayElt},
+        tag, Custom, DynBox, OCamlArray, OCamlArrayElement, OCamlBytes, OCamlFloat,
+        OCamlFloatArray, OCamlInt32, OCamlInt64, OCamlList, OCamlNativeInt, PolyVariant, RawOCaml,
+        UNIT,
     },
-    runtime::OCamlRuntime,
+    runtime::{converting_from_ocaml, OCamlRuntime},
     value::OCaml,
 };
-use core::{any::Any, cell::UnsafeCell, marker::PhantomData, mem, pin::Pin, ptr};
+use core::{
+    any::Any,
+    cell::UnsafeCell,
+    ffi::c_void,
+    marker::PhantomData,
+    mem,
+    pin::Pin,
+    ptr,
+    sync::atomic::{AtomicIsize, Ordering},
+};
 pub use ocaml_sys::{
     caml_alloc, local_roots as ocaml_sys_local_roots, set_local_roots as ocaml_sys_set_local_roots,
     store_field,
 };
 use ocaml_sys::{
     caml_alloc_string, caml_alloc_tuple, caml_copy_double, caml_copy_int32, caml_copy_int64,
-    custom_operations, string_val, Size,
+    caml_copy_nativeint, caml_failwith_value, caml_string_length, custom_operations, is_block,
+    string_val, tag_val, wosize_val, Size,
 };
+use std::sync::Once;
 
 pub struct OCamlCell<T> {
     cell: UnsafeCell<RawOCaml>,
     _marker: PhantomData<T>,
 }
 
-static_assertions::assert_eq_size!(OCamlCell<bool>, OCaml<'static, bool>, RawOCaml);
+static_assertions::assert_eq_size!(OCamlCell<bool>, RawOCaml);
+// Debug builds also store in `OCaml` values the generation used to detect stale values
+#[cfg(not(debug_assertions))]
+static_assertions::assert_eq_size!(OCaml<'static, bool>, RawOCaml);
 
 /// An `OCamlRef<T>` is a reference to a location containing a [`OCaml`]`<T>` value.
 ///
@@ -43,7 +60,15 @@ impl<T> OCamlCell<T> {
     where
         RustT: FromOCaml<T>,
     {
-        RustT::from_ocaml(cr.get(self))
+        converting_from_ocaml(|| RustT::from_ocaml(cr.get(self)))
+    }
+
+    /// Converts this value into a Rust value, when it can be represented by `RustT`.
+    pub fn try_to_rust<RustT>(&self, cr: &OCamlRuntime) -> Result<RustT, RustT::Error>
+    where
+        RustT: TryFromOCaml<T>,
+    {
+        converting_from_ocaml(|| RustT::try_from_ocaml(cr.get(self)))
     }
 
     /// Borrows the raw value contained in this root.
@@ -56,13 +81,105 @@ impl<T> OCamlCell<T> {
     }
 }
 
+extern "C" {
+    fn caml_ba_sub(vb: RawOCaml, vofs: RawOCaml, vlen: RawOCaml) -> RawOCaml;
+}
+
+impl<A: BigarrayElt> OCamlCell<Array1<A>> {
+    /// Returns a bigarray of `len` elements that is a view of this one, starting at
+    /// element `offset`, like `Bigarray.Array1.sub` does on the OCaml side.
+    ///
+    /// The elements are not copied, both bigarrays share the same data, and writes to
+    /// one of them are visible through the other. The data remains alive for as long as
+    /// either bigarray is alive.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `offset + len` is greater than the length of this bigarray.
+    pub fn sub<'a>(
+        &self,
+        cr: &'a mut OCamlRuntime,
+        offset: usize,
+        len: usize,
+    ) -> OCaml<'a, Array1<A>> {
+        let parent_len = cr.get(self).len();
+        assert!(
+            matches!(offset.checked_add(len), Some(end) if end <= parent_len),
+            "bigarray view out of bounds, offset={} len={} but the bigarray has {} elements",
+            offset,
+            len,
+            parent_len
+        );
+        unsafe {
+            let sub = caml_ba_sub(
+                self.get_raw(),
+                OCaml::of_i64_unchecked(offset as i64).raw(),
+                OCaml::of_i64_unchecked(len as i64).raw(),
+            );
+            OCaml::after_safepoint(cr, sub)
+        }
+    }
+}
+
 pub fn alloc_bytes<'a>(cr: &'a mut OCamlRuntime, s: &[u8]) -> OCaml<'a, OCamlBytes> {
     unsafe {
         let len = s.len();
         let value = caml_alloc_string(len);
         let ptr = string_val(value);
         core::ptr::copy_nonoverlapping(s.as_ptr(), ptr, len);
-        OCaml::new(cr, value)
+        OCaml::after_safepoint(cr, value)
+    }
+}
+
+/// Splits `data` into chunks of `chunk_size` bytes and allocates an OCaml list
+/// of `bytes` values with them, in order.
+///
+/// The last chunk is shorter than `chunk_size` when the length of `data` is not a
+/// multiple of it. An empty `data` results in an empty list.
+///
+/// # Panics
+///
+/// Panics if `chunk_size` is 0.
+pub fn alloc_bytes_chunked<'a>(
+    cr: &'a mut OCamlRuntime,
+    data: &[u8],
+    chunk_size: usize,
+) -> OCaml<'a, OCamlList<OCamlBytes>> {
+    assert!(chunk_size != 0, "chunk size must be non-zero");
+    let mut result = BoxRoot::new(OCaml::nil());
+    for chunk in data.chunks(chunk_size).rev() {
+        let cons = unsafe { alloc_cons_placeholder(cr, &result) };
+        result.keep(cons);
+        unsafe {
+            let ov = alloc_bytes(cr, chunk).get_raw();
+            store_raw_field_at(cr, &result, 0, ov);
+        }
+    }
+    cr.get(&result)
+}
+
+/// Copies `src` into the existing OCaml `bytes` value `dst`, starting at byte `offset`.
+///
+/// Nothing is allocated, so a single buffer can be reused for many messages. The bytes
+/// are modified in place: any OCaml code holding `dst` sees the new contents, which is
+/// only safe when no OCaml code is reading the buffer at the same time (for example
+/// from another domain or thread).
+///
+/// # Panics
+///
+/// Panics if `src` doesn't fit in `dst` at `offset`.
+pub fn blit_str_into(_cr: &OCamlRuntime, dst: &mut OCaml<OCamlBytes>, offset: usize, src: &str) {
+    let dst_len = unsafe { caml_string_length(dst.raw()) };
+    assert!(
+        matches!(offset.checked_add(src.len()), Some(end) if end <= dst_len),
+        "copying {} bytes at offset {} into OCaml bytes of length {}",
+        src.len(),
+        offset,
+        dst_len
+    );
+    unsafe {
+        let ptr = string_val(dst.raw()).add(offset);
+        ptr::copy_nonoverlapping(src.as_ptr(), ptr, src.len());
     }
 }
 
@@ -72,25 +189,155 @@ pub fn alloc_string<'a>(cr: &'a mut OCamlRuntime, s: &str) -> OCaml<'a, String>
         let value = caml_alloc_string(len);
         let ptr = string_val(value);
         core::ptr::copy_nonoverlapping(s.as_ptr(), ptr, len);
-        OCaml::new(cr, value)
+        OCaml::after_safepoint(cr, value)
+    }
+}
+
+/// Create a new OCaml `float array` with the contents of `data`.
+///
+/// The floats are stored unboxed, in a block with the `Double_array_tag` tag. This
+/// assumes an OCaml runtime configured with flat float arrays, which is the default.
+pub fn alloc_float_array<'a>(cr: &'a mut OCamlRuntime, data: &[f64]) -> OCaml<'a, OCamlFloatArray> {
+    unsafe {
+        if data.is_empty() {
+            // Like OCaml's own `[||]`, the empty float array is the atom with tag 0
+            return OCaml::after_safepoint(cr, caml_alloc(0, 0));
+        }
+        let wosize = mem::size_of_val(data) / mem::size_of::<RawOCaml>();
+        let value = caml_alloc(wosize, tag::DOUBLE_ARRAY);
+        ptr::copy_nonoverlapping(data.as_ptr(), value as *mut f64, data.len());
+        OCaml::after_safepoint(cr, value)
+    }
+}
+
+/// Create a new OCaml record whose fields are all `float`, with the values of `fields`.
+///
+/// OCaml stores such records flat, like a `float array`: the fields are unboxed
+/// doubles in a block with the `Double_array_tag` tag, so they can't be built with
+/// [`alloc_tuple`] or [`RecordBuilder`].
+///
+/// # Safety
+///
+/// `T` must be an OCaml record type with exactly `fields.len()` fields, all of them
+/// of type `float`.
+///
+/// # Panics
+///
+/// Panics if `fields` is empty.
+pub unsafe fn alloc_float_record<'a, T>(cr: &'a mut OCamlRuntime, fields: &[f64]) -> OCaml<'a, T> {
+    assert!(
+        !fields.is_empty(),
+        "a float record must have at least one field"
+    );
+    let wosize = mem::size_of_val(fields) / mem::size_of::<RawOCaml>();
+    let value = caml_alloc(wosize, tag::DOUBLE_ARRAY);
+    ptr::copy_nonoverlapping(fields.as_ptr(), value as *mut f64, fields.len());
+    OCaml::after_safepoint(cr, value)
+}
+
+/// Allocates an OCaml `array` of `len` elements, all of them set to `()`.
+///
+/// The elements are then set with [`store_array_element`]. Unlike with
+/// [`store_field_no_barrier`], converting the elements between these calls is fine.
+///
+/// # Safety
+///
+/// Every element must be stored before the array is used as an array of `A`, unless
+/// `()` is a valid value of type `A`.
+pub unsafe fn alloc_array<A: OCamlArrayElement>(
+    cr: &mut OCamlRuntime,
+    len: usize,
+) -> OCaml<'_, OCamlArray<A>> {
+    // Like OCaml's own `[||]`, the empty array is the atom with tag 0
+    OCaml::after_safepoint(cr, caml_alloc(len, 0))
+}
+
+/// Stores `value` as the element `index` of `array`, going through the OCaml write
+/// barrier.
+///
+/// # Panics
+///
+/// Panics if `index` is out of bounds.
+pub fn store_array_element<A: OCamlArrayElement>(
+    cr: &mut OCamlRuntime,
+    array: OCamlRef<OCamlArray<A>>,
+    index: usize,
+    value: OCamlRef<A>,
+) {
+    unsafe {
+        let len = wosize_val(cr.get(array).get_raw());
+        assert!(
+            index < len,
+            "array index out of bounds: the length is {} but the index is {}",
+            len,
+            index
+        );
+        store_raw_field_at(cr, array, index, value.get_raw());
     }
 }
 
+/// Raises an OCaml `Failure` exception with `message`.
+///
+/// # Safety
+///
+/// This function must only be called from Rust code that has been called from OCaml
+/// (like the functions defined with [`ocaml_export!`](crate::ocaml_export)), the exception will be caught by
+/// the OCaml caller. The Rust stack frames between this call and the OCaml caller are
+/// exited without running any destructors, so the values owned by them are leaked
+/// (including the roots of arguments of [`ocaml_export!`](crate::ocaml_export) functions). Locks or other
+/// resources must not be held at that point.
+pub unsafe fn raise_failure(cr: &mut OCamlRuntime, message: &str) -> ! {
+    let message = alloc_string(cr, message).get_raw();
+    raise_failure_raw(message)
+}
+
+// Like `raise_failure` but for an already allocated OCaml string.
+pub(crate) unsafe fn raise_failure_raw(message: RawOCaml) -> ! {
+    caml_failwith_value(message);
+    unreachable!("caml_failwith_value returned")
+}
+
 pub fn alloc_int32(cr: &mut OCamlRuntime, i: i32) -> OCaml<OCamlInt32> {
-    unsafe { OCaml::new(cr, caml_copy_int32(i)) }
+    unsafe { OCaml::after_safepoint(cr, caml_copy_int32(i)) }
 }
 
 pub fn alloc_int64(cr: &mut OCamlRuntime, i: i64) -> OCaml<OCamlInt64> {
-    unsafe { OCaml::new(cr, caml_copy_int64(i)) }
+    unsafe { OCaml::after_safepoint(cr, caml_copy_int64(i)) }
+}
+
+pub fn alloc_nativeint<'a>(cr: &'a mut OCamlRuntime, i: isize) -> OCaml<'a, OCamlNativeInt> {
+    unsafe { OCaml::after_safepoint(cr, caml_copy_nativeint(i)) }
 }
 
 pub fn alloc_double(cr: &mut OCamlRuntime, d: f64) -> OCaml<OCamlFloat> {
-    unsafe { OCaml::new(cr, caml_copy_double(d)) }
+    unsafe { OCaml::after_safepoint(cr, caml_copy_double(d)) }
 }
 
-// TODO: it is possible to directly alter the fields memory upon first allocation of
-// small values (like tuples and conses are) without going through `caml_modify` to get
-// a little bit of extra performance.
+/// Stores `value` in the field `offset` of `block` without going through the OCaml write
+/// barrier, to fill a block that was just allocated.
+///
+/// The write barrier (`caml_modify`) keeps track of the pointers from blocks in the major
+/// heap to values in the minor heap. Blocks that are still in the minor heap don't need
+/// it, their fields can be written directly, which is what OCaml itself does to
+/// initialize small blocks.
+///
+/// Constructors that convert their fields one by one after allocating the block, like
+/// the ones of tuples and records, can't use this: converting a field can allocate,
+/// which can run a minor collection and move the block to the major heap.
+///
+/// # Safety
+///
+/// - `block` must have been allocated in the minor heap, with [`caml_alloc`] or
+///   `caml_alloc_tuple`, with a size of at most `Max_young_wosize` (256) words (larger
+///   blocks are allocated directly in the major heap).
+/// - Nothing may have been allocated in the OCaml heap, and no OCaml code may have run,
+///   since `block` was allocated.
+/// - `block` must have a tag below `No_scan_tag`, and more than `offset` fields.
+#[doc(hidden)]
+#[inline]
+pub unsafe fn store_field_no_barrier(block: RawOCaml, offset: Size, value: RawOCaml) {
+    *(block as *mut RawOCaml).add(offset) = value;
+}
 
 pub fn alloc_some<'a, 'b, A>(
     cr: &'a mut OCamlRuntime,
@@ -98,8 +345,8 @@ pub fn alloc_some<'a, 'b, A>(
 ) -> OCaml<'a, Option<A>> {
     unsafe {
         let ocaml_some = caml_alloc(1, tag::SOME);
-        store_field(ocaml_some, 0, value.get_raw());
-        OCaml::new(cr, ocaml_some)
+        store_field_no_barrier(ocaml_some, 0, value.get_raw());
+        OCaml::after_safepoint(cr, ocaml_some)
     }
 }
 
@@ -109,8 +356,8 @@ pub fn alloc_ok<'a, 'b, A, Err>(
 ) -> OCaml<'a, Result<A, Err>> {
     unsafe {
         let ocaml_ok = caml_alloc(1, tag::TAG_OK);
-        store_field(ocaml_ok, 0, value.get_raw());
-        OCaml::new(cr, ocaml_ok)
+        store_field_no_barrier(ocaml_ok, 0, value.get_raw());
+        OCaml::after_safepoint(cr, ocaml_ok)
     }
 }
 
@@ -120,15 +367,15 @@ pub fn alloc_error<'a, 'b, A, Err>(
 ) -> OCaml<'a, Result<A, Err>> {
     unsafe {
         let ocaml_err = caml_alloc(1, tag::TAG_ERROR);
-        store_field(ocaml_err, 0, err.get_raw());
-        OCaml::new(cr, ocaml_err)
+        store_field_no_barrier(ocaml_err, 0, err.get_raw());
+        OCaml::after_safepoint(cr, ocaml_err)
     }
 }
 
 #[doc(hidden)]
 pub unsafe fn alloc_tuple<T>(cr: &mut OCamlRuntime, size: usize) -> OCaml<T> {
     let ocaml_tuple = caml_alloc_tuple(size);
-    OCaml::new(cr, ocaml_tuple)
+    OCaml::after_safepoint(cr, ocaml_tuple)
 }
 
 /// List constructor
@@ -141,12 +388,73 @@ pub fn alloc_cons<'a, 'b, A>(
 ) -> OCaml<'a, OCamlList<A>> {
     unsafe {
         let ocaml_cons = caml_alloc(2, tag::CONS);
-        store_field(ocaml_cons, 0, head.get_raw());
-        store_field(ocaml_cons, 1, tail.get_raw());
-        OCaml::new(cr, ocaml_cons)
+        store_field_no_barrier(ocaml_cons, 0, head.get_raw());
+        store_field_no_barrier(ocaml_cons, 1, tail.get_raw());
+        OCaml::after_safepoint(cr, ocaml_cons)
     }
 }
 
+/// Variant constructor
+///
+/// Allocates the block of a non-constant variant constructor with tag `tag`, with
+/// room for `fields` values.
+///
+/// # Safety
+///
+/// All fields must be set (through [`store_raw_field_at`]) before the value is used,
+/// and the tag must be below `No_scan_tag`.
+pub unsafe fn alloc_variant<'a, A>(
+    cr: &'a mut OCamlRuntime,
+    tag: u8,
+    fields: usize,
+) -> OCaml<'a, A> {
+    let ocaml_block = caml_alloc(fields, tag);
+    OCaml::after_safepoint(cr, ocaml_block)
+}
+
+/// Polymorphic variant constructor
+///
+/// Allocates the OCaml polymorphic variant with tag `hash` and argument `payload`,
+/// like `` `Circle 1.0 ``. Constructors with more than one argument take a tuple as
+/// payload, and constructors without arguments are just the `hash` value itself, they
+/// don't need to be allocated.
+///
+/// # Safety
+///
+/// `hash` must be the hash of the constructor name, as returned by OCaml's
+/// `caml_hash_variant`.
+pub unsafe fn alloc_poly_variant<'a, 'b, P>(
+    cr: &'a mut OCamlRuntime,
+    hash: RawOCaml,
+    payload: OCamlRef<'b, P>,
+) -> OCaml<'a, PolyVariant> {
+    let ocaml_block = caml_alloc(2, tag::TAG_POLYMORPHIC_VARIANT);
+    store_field_no_barrier(ocaml_block, 0, hash);
+    store_field_no_barrier(ocaml_block, 1, payload.get_raw());
+    OCaml::after_safepoint(cr, ocaml_block)
+}
+
+/// List constructor with a placeholder head
+///
+/// Build a new list from a tail list, with `()` as the head. The head must be
+/// replaced with an actual value of type `A` (through [`store_raw_field_at`])
+/// before the list is used.
+pub unsafe fn alloc_cons_placeholder<'a, 'b, A>(
+    cr: &'a mut OCamlRuntime,
+    tail: OCamlRef<'b, OCamlList<A>>,
+) -> OCaml<'a, OCamlList<A>> {
+    let ocaml_cons = caml_alloc(2, tag::CONS);
+    store_field_no_barrier(ocaml_cons, 0, UNIT);
+    store_field_no_barrier(ocaml_cons, 1, tail.get_raw());
+    OCaml::after_safepoint(cr, ocaml_cons)
+}
+
+/// Stores a raw OCaml value in a field of an OCaml block.
+///
+/// # Safety
+///
+/// `block` must be a block with more than `offset` fields, and `raw_value` must be a
+/// valid OCaml value that has not been invalidated by an allocation since it was obtained.
 #[inline]
 pub unsafe fn store_raw_field_at<A>(
     cr: &mut OCamlRuntime,
@@ -157,6 +465,251 @@ pub unsafe fn store_raw_field_at<A>(
     store_field(cr.get(block).get_raw(), offset, raw_value);
 }
 
+extern "C" {
+    fn caml_obj_dup(v: RawOCaml) -> RawOCaml;
+}
+
+// Tag of effect continuations in OCaml 5, which must not be duplicated. Before OCaml 5
+// it is the tag of ordinary blocks.
+const CONT_TAG: tag::Tag = 245;
+
+// Whether the OCaml runtime has continuations. The version is not known when building
+// with the `without-ocamlopt` feature (it is empty then), in which case it is assumed
+// that it does, so that continuations are never duplicated.
+fn has_continuations() -> bool {
+    let major = ocaml_sys::VERSION.split('.').next();
+    !matches!(major.and_then(|major| major.parse::<u32>().ok()), Some(major) if major < 5)
+}
+
+// Blocks that are duplicated by `deep_copy`, the rest are shared with the original.
+fn is_copied_block(v: RawOCaml) -> bool {
+    is_block(v) && {
+        let tag = unsafe { tag_val(v) };
+        tag < CONT_TAG
+            || (tag == CONT_TAG && !has_continuations())
+            || tag == tag::STRING
+            || tag == tag::DOUBLE
+            || tag == tag::DOUBLE_ARRAY
+    }
+}
+
+/// Returns a structurally independent copy of `value`, rooted.
+///
+/// Rooting a value, with [`BoxRoot`] or an [`OCamlRef`], keeps it alive and reachable
+/// when the GC moves it, but it is still the same value: changes made to it (to the
+/// contents of a `ref` or an array, for example) are seen through every root. The copy
+/// returned here is made of new blocks instead, the ones of `value` are duplicated (with
+/// `caml_obj_dup`) recursively, so changes made to the original after the copy don't
+/// affect it, nor the other way around.
+///
+/// Blocks are copied with their contents: tuples, records, variants, lists, arrays,
+/// strings, and boxed floats. Closures, objects, lazy values, custom blocks (like
+/// bigarrays or boxed Rust values) and abstract blocks are not copied, the copy refers
+/// to the same ones as the original. Neither are OCaml 5 continuations, which share their
+/// tag (245) with ordinary blocks in earlier versions. When the OCaml version is not known
+/// at build time (with the `without-ocamlopt` feature), blocks with that tag are never
+/// copied.
+///
+/// Values that are reachable more than once from `value` are copied once for each path
+/// that leads to them, so `value` must not be cyclic.
+pub fn deep_copy<T>(cr: &mut OCamlRuntime, value: OCamlRef<T>) -> BoxRoot<T> {
+    let copy: BoxRoot<T> = unsafe {
+        let raw = value.get_raw();
+        if !is_copied_block(raw) {
+            return BoxRoot::new(cr.get(value));
+        }
+        BoxRoot::new(OCaml::after_safepoint(cr, caml_obj_dup(raw)))
+    };
+    // Copies whose fields still refer to the blocks of the original
+    let mut pending: Vec<BoxRoot<()>> = Vec::new();
+    unsafe {
+        let raw = copy.get_raw();
+        if tag_val(raw) < tag::NO_SCAN {
+            pending.push(BoxRoot::new(OCaml::new(cr, raw)));
+        }
+    }
+    while let Some(block) = pending.pop() {
+        let size = unsafe { wosize_val(block.get_raw()) };
+        for i in 0..size {
+            unsafe {
+                let field = *ocaml_sys::field(block.get_raw(), i);
+                if !is_copied_block(field) {
+                    continue;
+                }
+                let field_copy = caml_obj_dup(field);
+                let scan = tag_val(field_copy) < tag::NO_SCAN;
+                let field_copy: BoxRoot<()> = BoxRoot::new(OCaml::after_safepoint(cr, field_copy));
+                store_field(block.get_raw(), i, field_copy.get_raw());
+                if scan {
+                    pending.push(field_copy);
+                }
+            }
+        }
+    }
+    copy
+}
+
+/// Builds an OCaml record (or tuple) field by field.
+///
+/// The block of the record stays rooted while it is being built, and each value is
+/// stored in it right after its conversion.
+///
+/// The record is built as a regular block, with a boxed value in each field. Records
+/// whose fields are all `float` are stored flat by OCaml instead, as unboxed doubles:
+/// they must not be built with this type, but with [`alloc_float_record`].
+///
+/// ```
+/// # use ocaml_interop::*;
+/// # fn example(cr: &mut OCamlRuntime) {
+/// // For an OCaml record type like `type t = { id: int; name: string; score: float }`
+/// struct T;
+///
+/// let mut builder: RecordBuilder<T> = RecordBuilder::new(cr, 3);
+/// builder.set_field::<OCamlInt, _>(0, 1i64);
+/// builder.set_field::<String, _>(1, "one");
+/// builder.set_field::<OCamlFloat, _>(2, 1.5f64);
+/// let record: OCaml<T> = builder.build();
+/// # }
+/// ```
+pub struct RecordBuilder<'a, T: 'static> {
+    cr: &'a mut OCamlRuntime,
+    block: BoxRoot<T>,
+    is_set: Vec<bool>,
+}
+
+impl<'a, T> RecordBuilder<'a, T> {
+    /// Allocates a record with `size` fields.
+    pub fn new(cr: &'a mut OCamlRuntime, size: usize) -> Self {
+        let block = BoxRoot::new(unsafe { alloc_tuple(cr, size) });
+        RecordBuilder {
+            cr,
+            block,
+            is_set: vec![false; size],
+        }
+    }
+
+    /// Converts `value` and stores it in the field at `index`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is not lower than the number of fields of the record.
+    pub fn set_field<OCamlF, F>(&mut self, index: usize, value: F)
+    where
+        F: ToOCaml<OCamlF>,
+    {
+        assert!(
+            index < self.is_set.len(),
+            "field index {} out of bounds for a record of {} fields",
+            index,
+            self.is_set.len()
+        );
+        unsafe {
+            let field_val = value.to_ocaml(self.cr).get_raw();
+            store_raw_field_at(self.cr, &self.block, index, field_val);
+        }
+        self.is_set[index] = true;
+    }
+
+    /// Returns the built record.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any of the fields of the record has not been set.
+    pub fn build(self) -> OCaml<'a, T> {
+        if let Some(index) = self.is_set.iter().position(|is_set| !is_set) {
+            panic!("field {} of the record has not been set", index);
+        }
+        let RecordBuilder { cr, block, .. } = self;
+        cr.get(&block)
+    }
+}
+
+/// Builds an OCaml string piece by piece, without assembling it in Rust first.
+///
+/// The total length of the string, in bytes, must be known up front: the OCaml string
+/// is allocated with that length when the builder is created, and the pieces are then
+/// copied into it one after the other.
+///
+/// ```
+/// # use ocaml_interop::*;
+/// # fn example(cr: &mut OCamlRuntime) {
+/// let mut builder = OCamlStringBuilder::new(cr, 6);
+/// builder.write_str("foo");
+/// builder.write_str("bar");
+/// let s: OCaml<String> = builder.build();
+/// # }
+/// ```
+pub struct OCamlStringBuilder<'a> {
+    cr: &'a mut OCamlRuntime,
+    string: BoxRoot<String>,
+    len: usize,
+    offset: usize,
+}
+
+impl<'a> OCamlStringBuilder<'a> {
+    /// Allocates an OCaml string of `len` bytes to be filled by the builder.
+    pub fn new(cr: &'a mut OCamlRuntime, len: usize) -> Self {
+        let string = BoxRoot::new(unsafe { OCaml::after_safepoint(cr, caml_alloc_string(len)) });
+        OCamlStringBuilder {
+            cr,
+            string,
+            len,
+            offset: 0,
+        }
+    }
+
+    /// Returns the number of bytes that remain to be written.
+    pub fn remaining(&self) -> usize {
+        self.len - self.offset
+    }
+
+    /// Appends `s` to the string.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `s` is longer than the remaining length of the string.
+    pub fn write_str(&mut self, s: &str) {
+        self.write_bytes(s.as_bytes())
+    }
+
+    /// Appends `bytes` to the string.
+    ///
+    /// OCaml strings may hold arbitrary bytes, nothing checks that the bytes written form
+    /// valid UTF-8 once put together.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bytes` is longer than the remaining length of the string.
+    pub fn write_bytes(&mut self, bytes: &[u8]) {
+        assert!(
+            bytes.len() <= self.remaining(),
+            "writing {} bytes into an OCaml string with {} bytes remaining",
+            bytes.len(),
+            self.remaining()
+        );
+        unsafe {
+            let ptr = string_val(self.string.get_raw()).add(self.offset);
+            ptr::copy_nonoverlapping(bytes.as_ptr(), ptr, bytes.len());
+        }
+        self.offset += bytes.len();
+    }
+
+    /// Returns the built string.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the string has not been written up to its full length.
+    pub fn build(self) -> OCaml<'a, String> {
+        assert!(
+            self.remaining() == 0,
+            "{} bytes of the OCaml string have not been written",
+            self.remaining()
+        );
+        let OCamlStringBuilder { cr, string, .. } = self;
+        cr.get(&string)
+    }
+}
+
 const BOX_OPS_DYN_DROP: custom_operations = custom_operations {
     identifier: "_rust_box_dyn_drop\0".as_ptr() as *const ocaml_sys::Char,
     finalize: Some(drop_box_dyn),
@@ -206,7 +759,53 @@ pub fn alloc_box<A: 'static>(cr: &mut OCamlRuntime, data: A) -> OCaml<DynBox<A>>
         let box_ptr = ocaml_sys::field(oval, 1) as *mut B;
         std::ptr::write(box_ptr, Box::pin(data));
     }
-    unsafe { OCaml::new(cr, oval) }
+    unsafe { OCaml::after_safepoint(cr, oval) }
+}
+
+struct CustomBoxOps<A>(PhantomData<A>);
+
+impl<A: 'static> CustomBoxOps<A> {
+    // A reference, so that the operations are guaranteed to live in static memory
+    const OPS: &'static custom_operations = &custom_operations {
+        identifier: "_rust_custom_box\0".as_ptr() as *const ocaml_sys::Char,
+        finalize: Some(drop_custom_box::<A>),
+        compare: None,
+        hash: None,
+        serialize: None,
+        deserialize: None,
+        compare_ext: None,
+        fixed_length: ptr::null(),
+    };
+}
+
+extern "C" fn drop_custom_box<A>(oval: RawOCaml) {
+    unsafe {
+        let box_ptr = *(ocaml_sys::field(oval, 1) as *const *mut A);
+        drop(Box::from_raw(box_ptr));
+    }
+}
+
+/// Allocate an OCaml custom block holding `value`.
+///
+/// The custom block only holds a pointer to the boxed value, which is never moved
+/// by the GC: references to it obtained from the custom block (see
+/// [`OCaml::custom_ref`]) remain valid for as long as the block is alive.
+///
+/// The value is dropped by the finalizer of the custom block, when the GC collects
+/// it. When that happens is not specified, it may be long after the last reference to
+/// the block is gone, and it may not happen at all before the program exits. Drop
+/// implementations must not use the OCaml runtime.
+pub fn alloc_custom_box<'a, A: 'static>(
+    cr: &'a mut OCamlRuntime,
+    value: Box<A>,
+) -> OCaml<'a, Custom<A>> {
+    unsafe {
+        let oval =
+            ocaml_sys::caml_alloc_custom(CustomBoxOps::<A>::OPS, mem::size_of::<*mut A>(), 0, 1);
+        let box_ptr = ocaml_sys::field(oval, 1) as *mut *mut A;
+        ptr::write(box_ptr, Box::into_raw(value));
+        OCaml::after_safepoint(cr, oval)
+    }
 }
 
 /// Create a new OCaml `Bigarray.Array1` with the given type and size
@@ -230,5 +829,159 @@ pub fn alloc_bigarray1<'a, A: BigarrayElt>(
         let ba_meta_ptr = ocaml_sys::field(ocaml_ba, 1) as *const ocaml_sys::bigarray::Bigarray;
         core::ptr::copy_nonoverlapping(data.as_ptr(), (*ba_meta_ptr).data as *mut A, len);
     }
-    unsafe { OCaml::new(cr, ocaml_ba) }
+    unsafe { OCaml::after_safepoint(cr, ocaml_ba) }
+}
+
+/// Create a new OCaml `Bigarray.Array2` with the given type and dimensions
+///
+/// `data` is in row-major order (`c_layout`), and its length must be `rows * cols`.
+///
+/// Like with [`alloc_bigarray1`], memory belongs to the OCaml GC.
+///
+/// # Panics
+///
+/// Panics if `rows * cols` overflows, or is not the length of `data`.
+pub fn alloc_bigarray2<'a, A: BigarrayElt>(
+    cr: &'a mut OCamlRuntime,
+    rows: usize,
+    cols: usize,
+    data: &[A],
+) -> OCaml<'a, Array2<A>> {
+    let len = rows
+        .checked_mul(cols)
+        .expect("bigarray dimensions overflow usize");
+    assert_eq!(
+        len,
+        data.len(),
+        "bigarray dimensions don't match the length of the data"
+    );
+    let ocaml_ba;
+    unsafe {
+        // num_dims == 2, see alloc_bigarray1 for details
+        ocaml_ba =
+            ocaml_sys::bigarray::caml_ba_alloc_dims(A::KIND, 2, core::ptr::null_mut(), rows, cols);
+        let ba_meta_ptr = ocaml_sys::field(ocaml_ba, 1) as *const ocaml_sys::bigarray::Bigarray;
+        core::ptr::copy_nonoverlapping(data.as_ptr(), (*ba_meta_ptr).data as *mut A, data.len());
+    }
+    unsafe { OCaml::after_safepoint(cr, ocaml_ba) }
+}
+
+// Bigarrays that adopt the buffer of a `Vec`.
+//
+// OCaml releases the data of managed bigarrays with `free`, which can't be used on
+// memory that comes from the Rust global allocator. These bigarrays are flagged as
+// managed, so that sub-arrays created with `Array1.sub`, `slice`, `reshape`, etc. share
+// the proxy below instead of pointing into the buffer without keeping it alive, and their
+// operations are a copy of `caml_ba_ops` with a finalizer that gives the buffer back to
+// the `Vec` it comes from.
+//
+// The proxy starts with an extra reference that is never released by OCaml, so that the
+// finalizer of a sub-array never calls `free` on the data or the proxy. The buffer is
+// released by the finalizer of the original array, unless sub-arrays are still alive at
+// that point, in that case it is leaked.
+
+// Must start with the same fields as `struct caml_ba_proxy`.
+#[repr(C)]
+struct VecBigarrayProxy {
+    refcount: AtomicIsize,
+    data: *mut c_void,
+    size: usize,
+    capacity: usize,
+    drop_data: unsafe fn(*mut c_void, usize),
+}
+
+struct VecBigarrayOps(UnsafeCell<custom_operations>);
+
+unsafe impl Sync for VecBigarrayOps {}
+
+static VEC_BIGARRAY_OPS: VecBigarrayOps = VecBigarrayOps(UnsafeCell::new(custom_operations {
+    identifier: ptr::null(),
+    finalize: None,
+    compare: None,
+    hash: None,
+    serialize: None,
+    deserialize: None,
+    compare_ext: None,
+    fixed_length: ptr::null(),
+}));
+
+extern "C" {
+    static caml_ba_ops: custom_operations;
+}
+
+fn vec_bigarray_ops() -> *const custom_operations {
+    static INIT: Once = Once::new();
+
+    INIT.call_once(|| unsafe {
+        *VEC_BIGARRAY_OPS.0.get() = custom_operations {
+            finalize: Some(finalize_vec_bigarray),
+            ..caml_ba_ops
+        };
+    });
+
+    VEC_BIGARRAY_OPS.0.get()
+}
+
+unsafe fn drop_vec_data<A>(data: *mut c_void, capacity: usize) {
+    drop(Vec::from_raw_parts(data as *mut A, 0, capacity));
+}
+
+extern "C" fn finalize_vec_bigarray(oval: RawOCaml) {
+    unsafe {
+        let ba = ocaml_sys::field(oval, 1) as *const ocaml_sys::bigarray::Bigarray;
+        let proxy = (*ba).proxy as *mut VecBigarrayProxy;
+        // Only the extra reference is left once this one is released, no sub-array
+        // shares the data anymore.
+        if (*proxy).refcount.fetch_sub(1, Ordering::AcqRel) == 2 {
+            let proxy = Box::from_raw(proxy);
+            (proxy.drop_data)(proxy.data, proxy.capacity);
+        }
+    }
+}
+
+/// Create a new OCaml `Bigarray.Array1` that takes ownership of the buffer of `data`.
+///
+/// Unlike the conversion of slices into bigarrays, the elements are not copied: the
+/// bigarray points to the allocation of the vector, which stays where it is and is
+/// released through the Rust global allocator when the bigarray is collected by the GC.
+///
+/// The elements are laid out as the contiguous, aligned array of `A` that a `Vec<A>` is,
+/// which is also what OCaml expects for the kind of elements of `A` (see
+/// [`BigarrayElt`]), so no realignment or copy is needed, not even for large buffers of
+/// bytes. The spare capacity of the vector is kept with the buffer and released along
+/// with it.
+///
+/// The ownership of the buffer is transferred once, when the vector is moved into this
+/// function: from then on the buffer belongs to the bigarray, and the Rust side can only
+/// reach it through the returned value (for example with `as_slice`).
+///
+/// Sub-arrays obtained from the bigarray on the OCaml side (with `Array1.sub`,
+/// `Array1.slice`, `reshape`, etc.) share its data safely, but if any of them is still
+/// alive when the bigarray itself is collected the buffer is leaked instead of released.
+pub fn alloc_bigarray1_from_vec<'a, A: BigarrayElt>(
+    cr: &'a mut OCamlRuntime,
+    data: Vec<A>,
+) -> OCaml<'a, Array1<A>> {
+    let mut data = mem::ManuallyDrop::new(data);
+    let len = data.len();
+    let proxy = Box::new(VecBigarrayProxy {
+        refcount: AtomicIsize::new(2),
+        data: data.as_mut_ptr() as *mut c_void,
+        size: len * mem::size_of::<A>(),
+        capacity: data.capacity(),
+        drop_data: drop_vec_data::<A>,
+    });
+    unsafe {
+        // The data is not NULL, OCaml uses it as it is instead of allocating
+        let ocaml_ba = ocaml_sys::bigarray::caml_ba_alloc_dims(
+            A::KIND | ocaml_sys::bigarray::Managed::MANAGED as i32,
+            1,
+            proxy.data,
+            len,
+        );
+        let ba = ocaml_sys::field(ocaml_ba, 1) as *mut ocaml_sys::bigarray::Bigarray;
+        (*ba).proxy = Box::into_raw(proxy) as *const ocaml_sys::bigarray::BigarrayProxy;
+        *(ocaml_ba as *mut *const custom_operations) = vec_bigarray_ops();
+        OCaml::after_safepoint(cr, ocaml_ba)
+    }
 }
@@ -29,6 +29,30 @@ pub struct DynBox<A> {
     _marker: PhantomData<A>,
 }
 
+/// `OCaml<Custom<T>>` is an OCaml custom block holding a boxed Rust value of type `T`,
+/// that is opaque to the OCaml side.
+///
+/// To allocate one, use [`alloc_custom_box`][crate::alloc_custom_box].
+pub struct Custom<A> {
+    _marker: PhantomData<A>,
+}
+
+/// [`OCaml`]`<OCamlControlFlow<B, C>>` is a reference to an OCaml value of the variant
+/// type that mirrors Rust's `ControlFlow<B, C>`, declared as:
+///
+/// ```ocaml
+/// type ('b, 'c) control_flow = Continue of 'c | Break of 'b
+/// ```
+pub struct OCamlControlFlow<B, C> {
+    _marker: PhantomData<(B, C)>,
+}
+
+/// [`OCaml`]`<PolyVariant>` is a reference to an OCaml polymorphic variant value, like
+/// `` `Circle 1.0 `` or `` `Unit ``, without a more precise type.
+///
+/// Values of this type are allocated with [`alloc_poly_variant`](crate::alloc_poly_variant).
+pub struct PolyVariant {}
+
 /// [`OCaml`]`<OCamlBytes>` is a reference to an OCaml `bytes` value.
 ///
 /// # Note
@@ -40,11 +64,106 @@ pub struct OCamlBytes {}
 /// [`OCaml`]`<OCamlInt>` is an OCaml integer (tagged and unboxed) value.
 pub type OCamlInt = Intnat;
 
+/// Number of bits of an OCaml `int`, one less than the size of a machine word (63 on
+/// 64-bit platforms, 31 on 32-bit platforms).
+pub const OCAML_INT_BITS: u32 = usize::BITS - 1;
+
+/// [`OCaml`]`<OCamlChar>` is an OCaml `char` (unboxed, one byte) value.
+pub struct OCamlChar {}
+
 /// [`OCaml`]`<OCamlInt32>` is a reference to an OCaml `Int32.t` (boxed `int32`) value.
 pub struct OCamlInt32 {}
 
 /// [`OCaml`]`<OCamlInt64>` is a reference to an OCaml `Int64.t` (boxed `int64`) value.
 pub struct OCamlInt64 {}
 
+/// [`OCaml`]`<OCamlNativeInt>` is a reference to an OCaml `Nativeint.t` (boxed `nativeint`) value.
+pub struct OCamlNativeInt {}
+
+/// [`OCaml`]`<OCamlExn>` is a reference to an OCaml `exn` value.
+///
+/// It converts into an [`OCamlException`][crate::OCamlException], so that a
+/// `('a, exn) result` can be read as a `Result<A, OCamlException>`.
+pub struct OCamlExn {}
+
 /// [`OCaml`]`<OCamlFloat>` is a reference to an OCaml `float` (boxed `float`) value.
 pub struct OCamlFloat {}
+
+/// [`OCaml`]`<OCamlFloatArray>` is a reference to an OCaml `float array` value, in which
+/// the floats are stored unboxed.
+pub struct OCamlFloatArray {}
+
+/// [`OCaml`]`<OCamlArray<T>>` is a reference to an OCaml `array` containing values of
+/// type `T`, each one stored in a field of the array block.
+///
+/// Arrays of floats are stored unboxed by OCaml, use [`OCamlFloatArray`] for them instead.
+pub struct OCamlArray<A> {
+    _marker: PhantomData<A>,
+}
+
+/// OCaml types that are stored boxed, one per field, in an [`OCamlArray`].
+///
+/// This is every OCaml type except `float`: OCaml stores the elements of `float array`
+/// values unboxed, so they are represented by [`OCamlFloatArray`] instead, and
+/// `OCamlArray<OCamlFloat>` can't be built.
+///
+/// Implement it for the Rust types that stand for OCaml records or variants, like the
+/// ones used with [`impl_to_ocaml_record!`] or [`impl_to_ocaml_variant!`], to convert
+/// arrays of them.
+///
+/// # Safety
+///
+/// The OCaml type represented by the implementing type must not be `float`.
+pub unsafe trait OCamlArrayElement: 'static {}
+
+macro_rules! impl_ocaml_array_element {
+    ($(<$($param:ident),+> $typ:ty),+ $(,)?) => {
+        $(unsafe impl<$($param: 'static),+> OCamlArrayElement for $typ {})+
+    };
+    ($($typ:ty),+ $(,)?) => {
+        $(unsafe impl OCamlArrayElement for $typ {})+
+    };
+}
+
+impl_ocaml_array_element!(
+    (),
+    bool,
+    OCamlInt,
+    OCamlChar,
+    OCamlInt32,
+    OCamlInt64,
+    OCamlNativeInt,
+    String,
+    OCamlBytes,
+    OCamlExn,
+    OCamlFloatArray,
+    PolyVariant,
+);
+
+impl_ocaml_array_element!(
+    <A> Option<A>,
+    <A, E> Result<A, E>,
+    <A> OCamlList<A>,
+    <A> OCamlArray<A>,
+    <A> DynBox<A>,
+    <A> Custom<A>,
+    <B, C> OCamlControlFlow<B, C>,
+    <A, B> (A, B),
+    <A, B, C> (A, B, C),
+    <A, B, C, D> (A, B, C, D),
+    <A, B, C, D, E> (A, B, C, D, E),
+    <A, B, C, D, E, F> (A, B, C, D, E, F),
+    <A, B, C, D, E, F, G> (A, B, C, D, E, F, G),
+    <A, B, C, D, E, F, G, H> (A, B, C, D, E, F, G, H),
+    <A, B, C, D, E, F, G, H, I> (A, B, C, D, E, F, G, H, I),
+    <A, B, C, D, E, F, G, H, I, J> (A, B, C, D, E, F, G, H, I, J),
+    <A, B, C, D, E, F, G, H, I, J, K> (A, B, C, D, E, F, G, H, I, J, K),
+    <A, B, C, D, E, F, G, H, I, J, K, L> (A, B, C, D, E, F, G, H, I, J, K, L),
+    <A, B, C, D, E, F, G, H, I, J, K, L, M> (A, B, C, D, E, F, G, H, I, J, K, L, M),
+    <A, B, C, D, E, F, G, H, I, J, K, L, M, N> (A, B, C, D, E, F, G, H, I, J, K, L, M, N),
+    <A, B, C, D, E, F, G, H, I, J, K, L, M, N, O> (A, B, C, D, E, F, G, H, I, J, K, L, M, N, O),
+    <A, B, C, D, E, F, G, H, I, J, K, L, M, N, O, P> (A, B, C, D, E, F, G, H, I, J, K, L, M, N, O, P),
+);
+
+unsafe impl<A: bigarray::BigarrayElt + 'static> OCamlArrayElement for bigarray::Array1<A> {}
+unsafe impl<A: bigarray::BigarrayElt + 'static> OCamlArrayElement for bigarray::Array2<A> {}
@@ -18,6 +18,10 @@ pub unsafe trait BigarrayElt: Copy {
     const KIND: i32;
 }
 
+// Layout bit of the bigarray flags, not exported by ocaml-sys (`CAML_BA_FORTRAN_LAYOUT`
+// in OCaml's `bigarray.h`, a cleared bit means `c_layout`)
+pub(crate) const CAML_BA_FORTRAN_LAYOUT: ocaml_sys::Intnat = 0x100;
+
 // TODO:
 // assert that size_of::<$t>() matches caml_ba_element_size[$k]
 // Not sure we can check this at compile time,
@@ -46,3 +50,49 @@ make_kind!(char, CHAR);
 pub struct Array1<A: BigarrayElt> {
     _marker: PhantomData<A>,
 }
+
+pub struct Array2<A: BigarrayElt> {
+    _marker: PhantomData<A>,
+}
+
+/// A slice of row-major data with explicit dimensions, to be
+/// converted into an OCaml `Bigarray.Array2`.
+pub struct Matrix<'a, A: BigarrayElt> {
+    data: &'a [A],
+    rows: usize,
+    cols: usize,
+}
+
+impl<'a, A: BigarrayElt> Matrix<'a, A> {
+    /// Creates a `rows` × `cols` matrix view of `data`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `rows * cols` overflows, or is not the length of `data`.
+    pub fn new(data: &'a [A], rows: usize, cols: usize) -> Self {
+        let len = rows
+            .checked_mul(cols)
+            .expect("matrix dimensions overflow usize");
+        assert_eq!(
+            len,
+            data.len(),
+            "matrix dimensions don't match the length of the data"
+        );
+        Matrix { data, rows, cols }
+    }
+
+    /// Row-major data of this matrix
+    pub fn data(&self) -> &'a [A] {
+        self.data
+    }
+
+    /// Number of rows
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    /// Number of columns
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+}
@@ -1,8 +1,13 @@
 // Copyright (c) Viable Systems and TezEdge Contributors
 // SPDX-License-Identifier: MIT
 
-pub use ocaml_sys::{Tag, CLOSURE, NO_SCAN, STRING, TAG_CONS as CONS, TAG_SOME as SOME};
+pub use ocaml_sys::{
+    Tag, CLOSURE, DOUBLE, DOUBLE_ARRAY, LAZY, NO_SCAN, OBJECT, STRING, TAG_CONS as CONS,
+    TAG_SOME as SOME,
+};
 
 pub const TAG_POLYMORPHIC_VARIANT: Tag = 0;
 pub const TAG_OK: Tag = 0;
 pub const TAG_ERROR: Tag = 1;
+pub const TAG_CONTINUE: Tag = 0;
+pub const TAG_BREAK: Tag = 1;
@@ -2,9 +2,16 @@
 // SPDX-License-Identifier: MIT
 
 use ocaml_boxroot_sys::{boxroot_setup, boxroot_teardown};
-use std::marker::PhantomData;
+#[cfg(not(feature = "no-caml-startup"))]
+use std::ffi::CString;
+use std::{marker::PhantomData, panic::AssertUnwindSafe};
 
-use crate::{memory::OCamlRef, value::OCaml};
+use crate::{
+    memory::{alloc_string, raise_failure_raw, OCamlRef},
+    mlvalues::{OCamlFloat, OCamlInt, MAX_FIXNUM, MIN_FIXNUM, UNIT},
+    value::OCaml,
+    RawOCaml,
+};
 
 /// OCaml runtime handle.
 ///
@@ -25,19 +32,55 @@ impl OCamlRuntime {
         Self { _private: () }
     }
 
+    /// Initializes the OCaml runtime with the command line arguments `args`, and returns an
+    /// OCaml runtime handle.
+    ///
+    /// The arguments are passed to `caml_startup` as its `argv` and become `Sys.argv` on the
+    /// OCaml side, the first one being the program name (`"ocaml"` is used if `args` is
+    /// empty). They are kept for as long as the program runs.
+    ///
+    /// The OCaml runtime is initialized only once per process: if it has already been
+    /// initialized (by this function, [`init`](Self::init) or
+    /// [`init_persistent`](Self::init_persistent)), `args` is ignored. The runtime must be
+    /// initialized, and the handle used, from the thread that is going to run OCaml code.
+    ///
+    /// Once the handle is dropped, the OCaml runtime will be shutdown.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any of the arguments contains a NUL byte.
+    pub fn init_with_args(args: &[&str]) -> Self {
+        Self::startup(args);
+        Self { _private: () }
+    }
+
     /// Initializes the OCaml runtime.
     ///
     /// After the first invocation, this method does nothing.
     pub fn init_persistent() {
+        Self::startup(&["ocaml"]);
+    }
+
+    #[allow(unused_variables)]
+    fn startup(args: &[&str]) {
         #[cfg(not(feature = "no-caml-startup"))]
         {
             static INIT: std::sync::Once = std::sync::Once::new();
 
             INIT.call_once(|| {
-                let arg0 = "ocaml\0".as_ptr() as *const ocaml_sys::Char;
-                let c_args = vec![arg0, core::ptr::null()];
+                let args = if args.is_empty() { &["ocaml"] } else { args };
+                // The runtime keeps `argv` around to build `Sys.argv`, so it is never released
+                let c_args: Vec<*const ocaml_sys::Char> = args
+                    .iter()
+                    .map(|arg| {
+                        CString::new(*arg)
+                            .expect("OCaml runtime arguments can't contain NUL bytes")
+                            .into_raw() as *const ocaml_sys::Char
+                    })
+                    .chain(core::iter::once(core::ptr::null()))
+                    .collect();
                 unsafe {
-                    ocaml_sys::caml_startup(c_args.as_ptr());
+                    ocaml_sys::caml_startup(Box::leak(c_args.into_boxed_slice()).as_ptr());
                 }
             })
         }
@@ -45,6 +88,36 @@ impl OCamlRuntime {
         panic!("Rust code that is called from an OCaml program should not try to initialize the runtime.");
     }
 
+    /// Returns the largest value of an OCaml `int` (`Stdlib.max_int`) on this platform.
+    ///
+    /// Larger values can't be converted into [`OCamlInt`] without losing their top bits,
+    /// `OCamlInt64` can be used for them instead.
+    pub const fn max_int() -> i64 {
+        MAX_FIXNUM as i64
+    }
+
+    /// Returns the smallest value of an OCaml `int` (`Stdlib.min_int`) on this platform.
+    pub const fn min_int() -> i64 {
+        MIN_FIXNUM as i64
+    }
+
+    /// Returns the current counters of the OCaml GC, like OCaml's `Gc.quick_stat`.
+    ///
+    /// Comparing the counters before and after some code runs tells how much it
+    /// allocated in the OCaml heap. Reading the counters allocates a few words itself.
+    pub fn gc_stats(&mut self) -> GcStats {
+        let stat: OCaml<()> = unsafe { OCaml::after_safepoint(self, caml_gc_quick_stat(UNIT)) };
+        unsafe {
+            GcStats {
+                minor_words: stat.field::<OCamlFloat>(0).to_rust(),
+                promoted_words: stat.field::<OCamlFloat>(1).to_rust(),
+                major_words: stat.field::<OCamlFloat>(2).to_rust(),
+                minor_collections: stat.field::<OCamlInt>(3).to_rust(),
+                major_collections: stat.field::<OCamlInt>(4).to_rust(),
+            }
+        }
+    }
+
     /// Recover the runtime handle.
     ///
     /// This method is used internally, do not use directly in code, only when writing tests.
@@ -54,8 +127,21 @@ impl OCamlRuntime {
     /// This function is unsafe because the OCaml runtime handle should be obtained once
     /// upon initialization of the OCaml runtime and then passed around. This method exists
     /// only to ease the authoring of tests.
+    ///
+    /// In debug builds, this function panics when called during the conversion of an OCaml
+    /// value into a Rust value, because any use of the runtime at that point could make the
+    /// GC move or release the value being converted.
     #[inline(always)]
     pub unsafe fn recover_handle() -> &'static mut Self {
+        #[cfg(debug_assertions)]
+        IN_CONVERSION.with(|in_conversion| {
+            assert!(
+                !in_conversion.get(),
+                "the OCaml runtime handle was recovered during the conversion of an OCaml value \
+                 into a Rust value, the runtime must not be used (to allocate or to call OCaml \
+                 functions) during such conversions"
+            )
+        });
         static mut RUNTIME: OCamlRuntime = OCamlRuntime { _private: () };
         &mut RUNTIME
     }
@@ -68,11 +154,120 @@ impl OCamlRuntime {
         OCamlBlockingSection::new().perform(f)
     }
 
+    /// Calls `f`, and raises an OCaml `Failure` exception with the panic message if it panics.
+    ///
+    /// Unwinding from a Rust function into the OCaml code that called it is undefined
+    /// behaviour. Wrapping the body of such a function with this method turns panics into
+    /// OCaml exceptions instead.
+    ///
+    /// # Safety
+    ///
+    /// Same as [`raise_failure`](crate::raise_failure): this method must only be called from
+    /// Rust code that has been called from OCaml, and when `f` panics no destructors are
+    /// run for the values owned by the callers of this method.
+    pub unsafe fn catch_panic_and_raise<'a, T, F>(&'a mut self, f: F) -> T
+    where
+        F: FnOnce(&'a mut OCamlRuntime) -> T,
+    {
+        let cr: *mut OCamlRuntime = self;
+        match std::panic::catch_unwind(AssertUnwindSafe(|| f(&mut *cr))) {
+            Ok(result) => result,
+            Err(payload) => {
+                let message = if let Some(message) = payload.downcast_ref::<&str>() {
+                    alloc_string(&mut *cr, message).get_raw()
+                } else if let Some(message) = payload.downcast_ref::<String>() {
+                    alloc_string(&mut *cr, message).get_raw()
+                } else {
+                    alloc_string(&mut *cr, "Rust panic").get_raw()
+                };
+                drop(payload);
+                raise_failure_raw(message)
+            }
+        }
+    }
+
     /// Returns the OCaml valued to which this GC tracked reference points to.
     pub fn get<'tmp, T>(&'tmp self, reference: OCamlRef<T>) -> OCaml<'tmp, T> {
         OCaml {
             _marker: PhantomData,
             raw: unsafe { reference.get_raw() },
+            generation: Generation::current(),
+        }
+    }
+}
+
+/// Counters of the OCaml GC, returned by [`OCamlRuntime::gc_stats`].
+///
+/// The counters are totals since the OCaml runtime was started. Word counts are floats,
+/// as in OCaml's `Gc.stat`, so that they don't overflow on 32-bit platforms.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GcStats {
+    /// Number of words allocated in the minor heap.
+    pub minor_words: f64,
+    /// Number of words allocated in the minor heap that were moved to the major heap.
+    pub promoted_words: f64,
+    /// Number of words allocated in the major heap, including the promoted words.
+    pub major_words: f64,
+    /// Number of minor collections.
+    pub minor_collections: i64,
+    /// Number of major collection cycles completed.
+    pub major_collections: i64,
+}
+
+extern "C" {
+    fn caml_gc_quick_stat(unit: RawOCaml) -> RawOCaml;
+}
+
+// Number of points on this thread at which the OCaml GC may have run (allocations and
+// calls into OCaml made through this crate, and sections where the runtime is released),
+// only tracked in debug builds. While this thread holds the runtime, the GC can only run
+// at those points, even when it is triggered by another thread.
+#[cfg(debug_assertions)]
+thread_local! {
+    static GENERATION: std::cell::Cell<u64> = const { std::cell::Cell::new(0) };
+}
+
+// Generation in which an `OCaml` value was obtained. In debug builds, using a value that
+// is a pointer into the OCaml heap after the generation moved on panics, because the GC
+// may have moved or freed the block it points to. It is zero-sized in release builds.
+#[derive(Clone, Copy)]
+pub(crate) struct Generation {
+    #[cfg(debug_assertions)]
+    count: u64,
+}
+
+impl Generation {
+    #[inline(always)]
+    pub(crate) fn current() -> Self {
+        Generation {
+            #[cfg(debug_assertions)]
+            count: GENERATION.with(|generation| generation.get()),
+        }
+    }
+
+    // Starts a new generation, after a point where the GC may have run.
+    #[inline(always)]
+    pub(crate) fn advance() -> Self {
+        Generation {
+            #[cfg(debug_assertions)]
+            count: GENERATION.with(|generation| {
+                let count = generation.get() + 1;
+                generation.set(count);
+                count
+            }),
+        }
+    }
+
+    // Immediate values are not managed by the GC, so they never become stale.
+    #[inline(always)]
+    #[cfg_attr(not(debug_assertions), allow(unused_variables))]
+    pub(crate) fn check(self, raw: RawOCaml) {
+        #[cfg(debug_assertions)]
+        if ocaml_sys::is_block(raw) && self.count != Self::current().count {
+            panic!(
+                "use of a stale OCaml value, obtained before the OCaml runtime was used to \
+                 allocate or to call OCaml code (root it with a BoxRoot to keep it across calls)"
+            );
         }
     }
 }
@@ -86,6 +281,34 @@ impl Drop for OCamlRuntime {
     }
 }
 
+// Reentrancy checks
+
+#[cfg(debug_assertions)]
+thread_local! {
+    static IN_CONVERSION: std::cell::Cell<bool> = const { std::cell::Cell::new(false) };
+}
+
+/// Calls `f`, a conversion of an OCaml value into a Rust value, during which the OCaml
+/// runtime must not be used. In debug builds, recovering the runtime handle in `f` panics.
+#[inline(always)]
+pub(crate) fn converting_from_ocaml<T>(f: impl FnOnce() -> T) -> T {
+    #[cfg(debug_assertions)]
+    {
+        struct Reset(bool);
+
+        impl Drop for Reset {
+            fn drop(&mut self) {
+                IN_CONVERSION.with(|in_conversion| in_conversion.set(self.0));
+            }
+        }
+
+        let _reset = Reset(IN_CONVERSION.with(|in_conversion| in_conversion.replace(true)));
+        f()
+    }
+    #[cfg(not(debug_assertions))]
+    f()
+}
+
 struct OCamlBlockingSection {}
 
 impl OCamlBlockingSection {
@@ -105,6 +328,8 @@ impl OCamlBlockingSection {
 impl Drop for OCamlBlockingSection {
     fn drop(&mut self) {
         unsafe { ocaml_sys::caml_leave_blocking_section() };
+        // Other threads may have used the runtime in the meantime
+        Generation::advance();
     }
 }
 
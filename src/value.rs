@@ -3,14 +3,19 @@
 
 use crate::{
     boxroot::BoxRoot,
-    error::OCamlFixnumConversionError,
+    error::{OCamlCharConversionError, OCamlFixnumConversionError},
     memory::{alloc_box, OCamlCell},
     mlvalues::*,
-    FromOCaml, OCamlException, OCamlRef, OCamlRuntime,
+    runtime::{converting_from_ocaml, Generation},
+    FromOCaml, OCamlException, OCamlRef, OCamlRuntime, TryFromOCaml,
 };
 use core::any::Any;
 use core::borrow::Borrow;
-use core::{marker::PhantomData, ops::Deref, slice, str};
+use core::{
+    marker::PhantomData,
+    ops::{ControlFlow, Deref},
+    slice, str,
+};
 use ocaml_sys::{
     caml_callback2_exn, caml_callback3_exn, caml_callbackN_exn, caml_callback_exn,
     caml_string_length, int_val, val_int,
@@ -18,9 +23,17 @@ use ocaml_sys::{
 use std::pin::Pin;
 
 /// Representation of OCaml values.
+///
+/// A value is only valid until the OCaml runtime is used again, to allocate or to call
+/// OCaml code, because the GC may move or free it at that point. The lifetime `'a` is
+/// the borrow of the runtime handle, which enforces this at compile time. In debug
+/// builds, values that escape it (through `unsafe` code, or more than one runtime
+/// handle) are also detected at run time: using them panics.
+#[must_use]
 pub struct OCaml<'a, T: 'a> {
     pub(crate) _marker: PhantomData<&'a T>,
     pub(crate) raw: RawOCaml,
+    pub(crate) generation: Generation,
 }
 
 impl<'a, T> Clone for OCaml<'a, T> {
@@ -45,11 +58,23 @@ impl<'a, T> OCaml<'a, T> {
         OCaml {
             _marker: PhantomData,
             raw: x,
+            generation: Generation::current(),
+        }
+    }
+
+    // Like `new`, for a value that was just allocated or returned by OCaml code, the
+    // values obtained before it may have been moved or freed by the GC.
+    pub(crate) unsafe fn after_safepoint(_cr: &'a mut OCamlRuntime, x: RawOCaml) -> OCaml<'a, T> {
+        OCaml {
+            _marker: PhantomData,
+            raw: x,
+            generation: Generation::advance(),
         }
     }
 
     #[doc(hidden)]
     pub unsafe fn field<F>(&self, i: UIntnat) -> OCaml<'a, F> {
+        self.generation.check(self.raw);
         assert!(
             tag_val(self.raw) < tag::NO_SCAN,
             "unexpected OCaml value tag >= NO_SCAN"
@@ -61,6 +86,7 @@ impl<'a, T> OCaml<'a, T> {
         OCaml {
             _marker: PhantomData,
             raw: *(self.raw as *const RawOCaml).add(i),
+            generation: self.generation,
         }
     }
 
@@ -93,11 +119,13 @@ impl<'a, T> OCaml<'a, T> {
     where
         'a: 'b,
     {
+        self.generation.check(self.raw);
         let ptr = &self.raw as *const RawOCaml;
         unsafe { OCamlCell::create_ref(ptr) }
     }
 
     pub fn root(self) -> BoxRoot<T> {
+        self.generation.check(self.raw);
         BoxRoot::new(self)
     }
 
@@ -109,6 +137,7 @@ impl<'a, T> OCaml<'a, T> {
     /// after any call into the OCaml runtime. Great care must be taken when
     /// working with these values.
     pub unsafe fn raw(&self) -> RawOCaml {
+        self.generation.check(self.raw);
         self.raw
     }
 
@@ -117,7 +146,17 @@ impl<'a, T> OCaml<'a, T> {
     where
         RustT: FromOCaml<T>,
     {
-        RustT::from_ocaml(*self)
+        self.generation.check(self.raw);
+        converting_from_ocaml(|| RustT::from_ocaml(*self))
+    }
+
+    /// Converts this OCaml value into a Rust value, when it can be represented by `RustT`.
+    pub fn try_to_rust<RustT>(&self) -> Result<RustT, RustT::Error>
+    where
+        RustT: TryFromOCaml<T>,
+    {
+        self.generation.check(self.raw);
+        converting_from_ocaml(|| RustT::try_from_ocaml(*self))
     }
 
     /// Meant to match Data_custom_val from mlvalues.h
@@ -133,6 +172,7 @@ impl<'a, T> OCaml<'a, T> {
     /// after any call into the OCaml runtime, for example allocating
     /// OCaml values or calling OCaml functions
     pub unsafe fn custom_ptr_val<U>(&self) -> *const U {
+        self.generation.check(self.raw);
         ocaml_sys::field(self.raw, 1) as *const U
     }
 }
@@ -157,10 +197,24 @@ impl OCaml<'static, ()> {
         OCaml {
             _marker: PhantomData,
             raw: UNIT,
+            generation: Generation::current(),
         }
     }
 }
 
+impl<'a, T: 'static> OCaml<'a, Custom<T>> {
+    /// Returns a reference to the Rust value held by this custom block.
+    pub fn custom_ref(&self) -> &'a T {
+        unsafe { &**self.custom_ptr_val::<*const T>() }
+    }
+}
+
+impl<'a, A: 'static> Borrow<A> for OCaml<'a, Custom<A>> {
+    fn borrow(&self) -> &A {
+        self.custom_ref()
+    }
+}
+
 // Be careful about not deriving anything on OCaml to
 // uphold the Borrow contract on Eq/Ord/Hash
 impl<'a, A: 'static> Borrow<A> for OCaml<'a, DynBox<A>> {
@@ -180,13 +234,20 @@ impl<T> OCaml<'static, Option<T>> {
         OCaml {
             _marker: PhantomData,
             raw: NONE,
+            generation: Generation::current(),
         }
     }
 }
 
 impl<'a> OCaml<'a, String> {
     /// Returns an `[u8]` reference to the internal bytes of this value.
+    ///
+    /// No copy is made, the slice points into the OCaml heap. Like this value, it is
+    /// only valid while the OCaml runtime remains borrowed: it can't be used after the
+    /// runtime is used again (to allocate or to call an OCaml function), because the GC
+    /// may move or release the string at that point.
     pub fn as_bytes(&self) -> &'a [u8] {
+        self.generation.check(self.raw);
         let s = self.raw;
         unsafe {
             assert!(
@@ -218,7 +279,13 @@ impl<'a> OCaml<'a, String> {
 
 impl<'a> OCaml<'a, OCamlBytes> {
     /// Returns an `[u8]` reference to the internal bytes of this value.
+    ///
+    /// No copy is made, the slice points into the OCaml heap. Like this value, it is
+    /// only valid while the OCaml runtime remains borrowed: it can't be used after the
+    /// runtime is used again (to allocate or to call an OCaml function), because the GC
+    /// may move or release the string at that point.
     pub fn as_bytes(&self) -> &'a [u8] {
+        self.generation.check(self.raw);
         let s = self.raw;
         unsafe {
             assert!(
@@ -264,6 +331,7 @@ impl<'a> OCaml<'a, OCamlInt> {
         OCaml {
             _marker: PhantomData,
             raw: val_int(n as isize),
+            generation: Generation::current(),
         }
     }
 
@@ -280,15 +348,71 @@ impl<'a> OCaml<'a, OCamlInt> {
             Ok(OCaml {
                 _marker: PhantomData,
                 raw: unsafe { val_int(n as isize) },
+                generation: Generation::current(),
             })
         }
     }
 
+    /// Creates an OCaml int from an `u64`.
+    ///
+    /// The conversion fails if the `u64` value doesn't fit in an OCaml fixnum and
+    /// an error is returned instead.
+    pub fn of_u64(n: u64) -> Result<OCaml<'static, OCamlInt>, OCamlFixnumConversionError> {
+        if n > MAX_FIXNUM as u64 {
+            Err(OCamlFixnumConversionError::UnsignedInputTooBig(n))
+        } else {
+            Ok(OCaml {
+                _marker: PhantomData,
+                raw: unsafe { val_int(n as isize) },
+                generation: Generation::current(),
+            })
+        }
+    }
+
+    /// Creates an OCaml int from an `usize`.
+    ///
+    /// The conversion fails if the `usize` value doesn't fit in an OCaml fixnum and
+    /// an error is returned instead.
+    pub fn of_usize(n: usize) -> Result<OCaml<'static, OCamlInt>, OCamlFixnumConversionError> {
+        Self::of_u64(n as u64)
+    }
+
+    /// Creates an OCaml int flag from a Rust boolean, `1` for `true` and `0` for `false`.
+    ///
+    /// Use [`OCaml::of_bool`] for OCaml `bool` values.
+    pub fn of_bool_as_int(b: bool) -> OCaml<'static, OCamlInt> {
+        Self::of_i32(i32::from(b))
+    }
+
     /// Creates an OCaml int from an i32.
     pub fn of_i32(n: i32) -> OCaml<'static, OCamlInt> {
         OCaml {
             _marker: PhantomData,
             raw: unsafe { val_int(n as isize) },
+            generation: Generation::current(),
+        }
+    }
+}
+
+impl<'a> OCaml<'a, OCamlChar> {
+    /// Converts an OCaml char into a Rust `char`.
+    pub fn to_char(&self) -> char {
+        unsafe { int_val(self.raw) as u8 as char }
+    }
+
+    /// Creates an OCaml char from a Rust `char`.
+    ///
+    /// OCaml chars are a single byte, so the conversion fails with an error if
+    /// the code point of `c` is greater than `U+00FF` (outside of Latin-1).
+    pub fn of_char(c: char) -> Result<OCaml<'static, OCamlChar>, OCamlCharConversionError> {
+        if (c as u32) > 0xFF {
+            Err(OCamlCharConversionError::InputOutOfRange(c))
+        } else {
+            Ok(OCaml {
+                _marker: PhantomData,
+                raw: unsafe { val_int(c as isize) },
+                generation: Generation::current(),
+            })
         }
     }
 }
@@ -304,6 +428,7 @@ impl<'a> OCaml<'a, bool> {
         OCaml {
             _marker: PhantomData,
             raw: if b { TRUE } else { FALSE },
+            generation: Generation::current(),
         }
     }
 }
@@ -320,6 +445,8 @@ impl<'a, A> OCaml<'a, Option<A>> {
     }
 
     /// Converts an OCaml `Option<T>` value into a Rust `Option<OCaml<T>>`.
+    ///
+    /// The payload is only read when the value is a `Some` block, `None` doesn't have one.
     pub fn to_option(&self) -> Option<OCaml<'a, A>> {
         if self.is_none() {
             None
@@ -328,11 +455,40 @@ impl<'a, A> OCaml<'a, Option<A>> {
             Some(OCaml {
                 _marker: PhantomData,
                 raw: value.raw,
+                generation: value.generation,
             })
         }
     }
 }
 
+impl<'a, B, C> OCaml<'a, OCamlControlFlow<B, C>> {
+    /// Converts an OCaml `control_flow` value into a Rust `ControlFlow<OCaml<B>, OCaml<C>>`.
+    pub fn to_control_flow(&self) -> ControlFlow<OCaml<'a, B>, OCaml<'a, C>> {
+        match self.tag_value() {
+            tag::TAG_CONTINUE => {
+                let value: OCaml<C> = unsafe { self.field(0) };
+                ControlFlow::Continue(OCaml {
+                    _marker: PhantomData,
+                    raw: value.raw,
+                    generation: value.generation,
+                })
+            }
+            tag::TAG_BREAK => {
+                let value: OCaml<B> = unsafe { self.field(0) };
+                ControlFlow::Break(OCaml {
+                    _marker: PhantomData,
+                    raw: value.raw,
+                    generation: value.generation,
+                })
+            }
+            tag => panic!(
+                "Unexpected tag value for OCaml<OCamlControlFlow<...>>: {}",
+                tag
+            ),
+        }
+    }
+}
+
 impl<'a, A, Err> OCaml<'a, Result<A, Err>> {
     /// Returns true if this OCaml result value is an OCaml `Ok`.
     pub fn is_ok(&self) -> bool {
@@ -351,12 +507,14 @@ impl<'a, A, Err> OCaml<'a, Result<A, Err>> {
             Ok(OCaml {
                 _marker: PhantomData,
                 raw: value.raw,
+                generation: value.generation,
             })
         } else if self.is_error() {
             let value: OCaml<Err> = unsafe { self.field(0) };
             Err(OCaml {
                 _marker: PhantomData,
                 raw: value.raw,
+                generation: value.generation,
             })
         } else {
             panic!(
@@ -373,6 +531,7 @@ impl<'a, A> OCaml<'a, OCamlList<A>> {
         OCaml {
             _marker: PhantomData,
             raw: EMPTY_LIST,
+            generation: Generation::current(),
         }
     }
 
@@ -407,6 +566,56 @@ impl<'a, A> OCaml<'a, OCamlList<A>> {
             Some(unsafe { (self.field(0), self.field(1)) })
         }
     }
+
+    /// Returns the number of elements of an OCaml list.
+    ///
+    /// The list is walked until its end, this is O(n) in the length of the list.
+    pub fn len(&self) -> usize {
+        let mut len = 0;
+        let mut current = *self;
+        while let Some(tl) = current.tl() {
+            len += 1;
+            current = tl;
+        }
+        len
+    }
+
+    /// Returns the element at index `i` of an OCaml list, or `None` if the list has `i`
+    /// elements or less.
+    ///
+    /// The list is walked up to that element, this is O(i).
+    pub fn nth(&self, i: usize) -> Option<OCaml<'a, A>> {
+        let mut current = *self;
+        for _ in 0..i {
+            current = current.tl()?;
+        }
+        current.hd()
+    }
+
+    /// Returns an iterator over the elements of an OCaml list.
+    ///
+    /// The cons cells are walked lazily, one per call to `next`, so only the elements
+    /// that are consumed get visited and nothing is collected.
+    ///
+    /// Like any `OCaml<'a, T>` value, the iterator and its elements borrow the OCaml
+    /// runtime, so no allocation (or call into OCaml) can happen while iterating. To
+    /// keep an element around, root it or convert it into a Rust value first.
+    ///
+    /// ```
+    /// # use ocaml_interop::*;
+    /// # fn example(cr: &mut OCamlRuntime, list: OCamlRef<OCamlList<OCamlInt>>) {
+    /// let list = cr.get(list);
+    /// let first_three: i64 = list.iter().take(3).map(|n| n.to_i64()).sum();
+    /// # }
+    /// ```
+    pub fn iter(&self) -> impl Iterator<Item = OCaml<'a, A>> {
+        let mut current = *self;
+        core::iter::from_fn(move || {
+            let (hd, tl) = current.uncons()?;
+            current = tl;
+            Some(hd)
+        })
+    }
 }
 
 // Tuples
@@ -480,6 +689,104 @@ impl_tuple!(
     6: tuple_7 -> G,
     7: tuple_8 -> H,
     8: tuple_9 -> I);
+impl_tuple!(
+    0: fst -> A,
+    1: snd -> B,
+    2: tuple_3 -> C,
+    3: tuple_4 -> D,
+    4: tuple_5 -> E,
+    5: tuple_6 -> F,
+    6: tuple_7 -> G,
+    7: tuple_8 -> H,
+    8: tuple_9 -> I,
+    9: tuple_10 -> J);
+impl_tuple!(
+    0: fst -> A,
+    1: snd -> B,
+    2: tuple_3 -> C,
+    3: tuple_4 -> D,
+    4: tuple_5 -> E,
+    5: tuple_6 -> F,
+    6: tuple_7 -> G,
+    7: tuple_8 -> H,
+    8: tuple_9 -> I,
+    9: tuple_10 -> J,
+    10: tuple_11 -> K);
+impl_tuple!(
+    0: fst -> A,
+    1: snd -> B,
+    2: tuple_3 -> C,
+    3: tuple_4 -> D,
+    4: tuple_5 -> E,
+    5: tuple_6 -> F,
+    6: tuple_7 -> G,
+    7: tuple_8 -> H,
+    8: tuple_9 -> I,
+    9: tuple_10 -> J,
+    10: tuple_11 -> K,
+    11: tuple_12 -> L);
+impl_tuple!(
+    0: fst -> A,
+    1: snd -> B,
+    2: tuple_3 -> C,
+    3: tuple_4 -> D,
+    4: tuple_5 -> E,
+    5: tuple_6 -> F,
+    6: tuple_7 -> G,
+    7: tuple_8 -> H,
+    8: tuple_9 -> I,
+    9: tuple_10 -> J,
+    10: tuple_11 -> K,
+    11: tuple_12 -> L,
+    12: tuple_13 -> M);
+impl_tuple!(
+    0: fst -> A,
+    1: snd -> B,
+    2: tuple_3 -> C,
+    3: tuple_4 -> D,
+    4: tuple_5 -> E,
+    5: tuple_6 -> F,
+    6: tuple_7 -> G,
+    7: tuple_8 -> H,
+    8: tuple_9 -> I,
+    9: tuple_10 -> J,
+    10: tuple_11 -> K,
+    11: tuple_12 -> L,
+    12: tuple_13 -> M,
+    13: tuple_14 -> N);
+impl_tuple!(
+    0: fst -> A,
+    1: snd -> B,
+    2: tuple_3 -> C,
+    3: tuple_4 -> D,
+    4: tuple_5 -> E,
+    5: tuple_6 -> F,
+    6: tuple_7 -> G,
+    7: tuple_8 -> H,
+    8: tuple_9 -> I,
+    9: tuple_10 -> J,
+    10: tuple_11 -> K,
+    11: tuple_12 -> L,
+    12: tuple_13 -> M,
+    13: tuple_14 -> N,
+    14: tuple_15 -> O);
+impl_tuple!(
+    0: fst -> A,
+    1: snd -> B,
+    2: tuple_3 -> C,
+    3: tuple_4 -> D,
+    4: tuple_5 -> E,
+    5: tuple_6 -> F,
+    6: tuple_7 -> G,
+    7: tuple_8 -> H,
+    8: tuple_9 -> I,
+    9: tuple_10 -> J,
+    10: tuple_11 -> K,
+    11: tuple_12 -> L,
+    12: tuple_13 -> M,
+    13: tuple_14 -> N,
+    14: tuple_15 -> O,
+    15: tuple_16 -> P);
 
 impl<'a, A: bigarray::BigarrayElt> OCaml<'a, bigarray::Array1<A>> {
     /// Returns the number of items in `self`
@@ -500,6 +807,61 @@ impl<'a, A: bigarray::BigarrayElt> OCaml<'a, bigarray::Array1<A>> {
             slice::from_raw_parts((*ba).data as *const A, self.len())
         }
     }
+
+    /// Copies the underlying data into a new `Vec`
+    ///
+    /// Unlike the slice obtained from [`OCaml::as_slice`], the result is owned
+    /// and remains valid after the OCaml runtime is used again.
+    pub fn to_vec(&self) -> Vec<A> {
+        self.as_slice().to_vec()
+    }
+
+    /// Get underlying data as a mutable Rust slice, to write into the
+    /// OCaml-owned memory in place.
+    ///
+    /// # Safety
+    ///
+    /// No other view of the same bigarray data (another `OCaml` copy of this
+    /// value, a slice obtained from [`OCaml::as_slice`], or the OCaml side)
+    /// may be used while the returned slice is alive.
+    ///
+    /// The caller must not trigger an OCaml allocation while the slice is alive
+    /// either, the GC could finalize the bigarray if it is not rooted.
+    pub unsafe fn as_mut_slice<'b>(&'b mut self, _cr: &'b OCamlRuntime) -> &'b mut [A] {
+        let ba = self.custom_ptr_val::<ocaml_sys::bigarray::Bigarray>();
+        slice::from_raw_parts_mut((*ba).data as *mut A, self.len())
+    }
+}
+
+impl<'a, A: bigarray::BigarrayElt> OCaml<'a, bigarray::Array2<A>> {
+    /// Returns the number of rows in `self`
+    pub fn dim1(&self) -> usize {
+        let ba = unsafe { self.custom_ptr_val::<ocaml_sys::bigarray::Bigarray>() };
+        unsafe { *((*ba).dim.as_ptr() as *const usize) }
+    }
+
+    /// Returns the number of columns in `self`
+    pub fn dim2(&self) -> usize {
+        let ba = unsafe { self.custom_ptr_val::<ocaml_sys::bigarray::Bigarray>() };
+        unsafe { *((*ba).dim.as_ptr() as *const usize).add(1) }
+    }
+
+    /// Get underlying data as a flat, row-major Rust slice
+    ///
+    /// # Panics
+    ///
+    /// Panics if the bigarray has the `fortran_layout` (column-major) layout instead of
+    /// `c_layout`.
+    pub fn as_slice(&self) -> &[A] {
+        unsafe {
+            let ba = self.custom_ptr_val::<ocaml_sys::bigarray::Bigarray>();
+            assert!(
+                (*ba).flags & bigarray::CAML_BA_FORTRAN_LAYOUT == 0,
+                "bigarray has fortran_layout, only c_layout can be viewed as a row-major slice"
+            );
+            slice::from_raw_parts((*ba).data as *const A, self.dim1() * self.dim2())
+        }
+    }
 }
 
 // Functions
@@ -548,6 +910,13 @@ macro_rules! try_call_impl {
             #[allow(non_camel_case_types)]
             impl<'c, $($ot),+, RetT> BoxRoot<fn($($ot,)+) -> RetT> {
                 /// Calls the OCaml closure, converting the arguments to OCaml if necessary
+                ///
+                /// Each argument is either an [`OCamlRef`] to an OCaml value, or a reference
+                /// to a Rust value implementing [`ToOCaml`](crate::ToOCaml), that is converted
+                /// and rooted for the duration of the call.
+                ///
+                /// If the closure raises an OCaml exception, it is caught and returned as an
+                /// [`OCamlException`] error.
                 pub fn try_call<'a, 'b: 'a, $($argname),* $(,$rt)* >(
                     &self,
                     cr: &'c mut OCamlRuntime,
@@ -563,7 +932,7 @@ macro_rules! try_call_impl {
                         let ex = unsafe { OCamlException::of(extract_exception(result)) };
                         Err(ex)
                     } else {
-                        Ok(unsafe { OCaml::new(cr, result) })
+                        Ok(unsafe { OCaml::after_safepoint(cr, result) })
                     }
                 }
             }
@@ -573,6 +942,13 @@ macro_rules! try_call_impl {
             #[allow(non_camel_case_types)]
             impl<'c, $($ot2,)* RetT> BoxRoot<fn($($ot2,)*) -> RetT> {
                 /// Calls the OCaml closure, converting the arguments to OCaml if necessary
+                ///
+                /// Each argument is either an [`OCamlRef`] to an OCaml value, or a reference
+                /// to a Rust value implementing [`ToOCaml`](crate::ToOCaml), that is converted
+                /// and rooted for the duration of the call.
+                ///
+                /// If the closure raises an OCaml exception, it is caught and returned as an
+                /// [`OCamlException`] error.
                 pub fn try_call<'a, 'b: 'a, $($argname2),* $(,$rt2)* >(
                     &self,
                     cr: &'c mut OCamlRuntime,
@@ -592,7 +968,7 @@ macro_rules! try_call_impl {
                         let ex = unsafe { OCamlException::of(extract_exception(result)) };
                         Err(ex)
                     } else {
-                        Ok(unsafe { OCaml::new(cr, result) })
+                        Ok(unsafe { OCaml::after_safepoint(cr, result) })
                     }
                 }
             }
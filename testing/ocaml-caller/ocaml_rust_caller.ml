@@ -50,6 +50,16 @@ module Rust = struct
 
   external make_error : string -> (int, string) result = "rust_make_error"
 
+  external parse_int_or_fail : string -> int = "rust_parse_int_or_fail"
+
+  external div_or_fail : int -> int -> int = "rust_div_or_fail"
+
+  type counter
+
+  external make_counter : int -> counter = "rust_make_counter"
+
+  external counter_value : counter -> int = "rust_counter_value"
+
   external sleep_releasing : int -> unit = "rust_sleep_releasing"
 
   external sleep : int -> unit = "rust_sleep"
@@ -118,6 +128,23 @@ let test_make_error () =
   let result = Rust.make_error "error" in
   Alcotest.(check (result int string)) "Make an Error(string)" expected result
 
+let test_parse_int_or_fail () =
+  Alcotest.(check int) "Parse a valid int" 42 (Rust.parse_int_or_fail "42");
+  Alcotest.check_raises "Raise Failure for an invalid int"
+    (Failure "invalid digit found in string") (fun () ->
+      ignore (Rust.parse_int_or_fail "forty-two"))
+
+let test_div_or_fail () =
+  Alcotest.(check int) "Divide by a non-zero int" 7 (Rust.div_or_fail 42 6);
+  Alcotest.check_raises "Raise Failure for a panic" (Failure "attempt to divide by zero")
+    (fun () -> ignore (Rust.div_or_fail 42 0))
+
+let test_custom_box () =
+  let counter = Rust.make_counter 42 in
+  Gc.compact ();
+  Alcotest.(check int) "Read the state held by a custom block" 42
+    (Rust.counter_value counter)
+
 let test_interpret_movement () =
   let expected =
     [ "RotateLeft"; "Step(10)"; "Error unpacking"; "Error unpacking" ]
@@ -196,6 +223,9 @@ let () =
           test_case "Rust.make_some" `Quick test_make_some;
           test_case "Rust.make_ok" `Quick test_make_ok;
           test_case "Rust.make_error" `Quick test_make_error;
+          test_case "Rust.parse_int_or_fail" `Quick test_parse_int_or_fail;
+          test_case "Rust.div_or_fail" `Quick test_div_or_fail;
+          test_case "Rust.make_counter" `Quick test_custom_box;
           test_case "Rust.sleep_releasing" `Quick test_blocking_section;
           test_case "Rust.sleep" `Quick test_regular_section;
           test_case "Rust.string_of_movement" `Quick test_interpret_movement;
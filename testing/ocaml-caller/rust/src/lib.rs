@@ -2,8 +2,9 @@
 // SPDX-License-Identifier: MIT
 
 use ocaml_interop::{
-    ocaml_export, ocaml_unpack_polymorphic_variant, ocaml_unpack_variant, OCaml, OCamlBytes,
-    OCamlFloat, OCamlInt, OCamlInt32, OCamlInt64, OCamlList, OCamlRef, ToOCaml,
+    alloc_custom_box, ocaml_export, ocaml_unpack_polymorphic_variant, ocaml_unpack_variant, Custom,
+    OCaml, OCamlBytes, OCamlFloat, OCamlInt, OCamlInt32, OCamlInt64, OCamlList, OCamlRef, ToOCaml,
+    ToOCamlOrRaise,
 };
 use std::{thread, time};
 
@@ -19,6 +20,10 @@ enum PolymorphicMovement {
     RotateRight,
 }
 
+struct Counter {
+    count: i64,
+}
+
 ocaml_export! {
     fn rust_twice(cr, num: OCamlRef<OCamlInt>) -> OCaml<OCamlInt> {
         let num: i64 = num.to_rust(cr);
@@ -98,6 +103,29 @@ ocaml_export! {
         error_value.to_ocaml(cr)
     }
 
+    fn rust_parse_int_or_fail(cr, s: OCamlRef<String>) -> OCaml<OCamlInt> {
+        // Nothing that needs to be dropped may be alive when the exception is raised
+        let result = cr.get(s).as_str().parse::<i64>();
+        unsafe { result.to_ocaml_or_raise(cr) }
+    }
+
+    fn rust_div_or_fail(cr, dividend: OCamlRef<OCamlInt>, divisor: OCamlRef<OCamlInt>) -> OCaml<OCamlInt> {
+        let dividend: i64 = dividend.to_rust(cr);
+        let divisor: i64 = divisor.to_rust(cr);
+        // Dividing by zero panics, the panic is raised as a `Failure` exception
+        unsafe { cr.catch_panic_and_raise(|cr| (dividend / divisor).to_ocaml(cr)) }
+    }
+
+    fn rust_make_counter(cr, count: OCamlRef<OCamlInt>) -> OCaml<Custom<Counter>> {
+        let count: i64 = count.to_rust(cr);
+        alloc_custom_box(cr, Box::new(Counter { count }))
+    }
+
+    fn rust_counter_value(cr, counter: OCamlRef<Custom<Counter>>) -> OCaml<OCamlInt> {
+        let count = cr.get(counter).custom_ref().count;
+        unsafe { OCaml::of_i64_unchecked(count) }
+    }
+
     fn rust_sleep_releasing(cr, millis: OCamlRef<OCamlInt>) {
         let millis: i64 = millis.to_rust(cr);
         cr.releasing_runtime(|| thread::sleep(time::Duration::from_millis(millis as u64)));
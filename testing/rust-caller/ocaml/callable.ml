@@ -10,6 +10,21 @@ type test_record = {
   t: int * float;
 }
 
+type point = {
+  x: int;
+  y: int;
+}
+
+type vec2 = {
+  vx: float;
+  vy: float;
+}
+
+type shape =
+  | Circle of float
+  | Rect of float * float
+  | Unit
+
 type movement =
   | Step of int
   | RotateLeft
@@ -21,6 +36,36 @@ type polymorphic_enum = [
   | `Multiple of (int * string)
 ]
 
+type poly_shape = [
+  | `Circle of float
+  | `Rect of float * float
+  | `Unit
+]
+
+type inventory_item = {
+  name : string;
+  count : int;
+  price : float;
+  tags : string list;
+  discount : float option;
+}
+
+type inventory_event = [
+  | `Started
+  | `Moved of int * int
+  | `Renamed of string
+  | `Resized of int * int
+]
+
+type json =
+  | Null
+  | Bool of bool
+  | Int of int
+  | Float of float
+  | String of string
+  | List of json list
+  | Assoc of (string * json) list
+
 let increment_bytes bytes first_n =
   let limit = (min (Bytes.length bytes) first_n) - 1 in
   for i = 0 to limit do
@@ -50,10 +95,43 @@ let make_ok x = Ok x
 
 let make_error x = Error x
 
+let sys_argv () = Array.to_list Sys.argv
+
+let make_counters n = List.init n (fun i -> (ref i, string_of_int i))
+
+let incr_counters counters = List.iter (fun (counter, _) -> incr counter) counters
+
+let counter_values counters = List.map (fun (counter, _) -> !counter) counters
+
+let ocaml_major_version () = int_of_string (List.hd (String.split_on_char '.' Sys.ocaml_version))
+
+let make_exn_error msg : (int, exn) result = Error (Failure msg)
+
+let make_not_found_error () : (int, exn) result = Error Not_found
+
 let stringify_record { i; f; i32; i64; s; t = (t1, t2); } =
   Printf.sprintf "{ i=%d; f=%.2f; i32=%ld; i64=%Ld; s=%s; t=(%d, %.2f) }"
     i f i32 i64 s t1 t2
 
+let stringify_point { x; y } =
+  Printf.sprintf "{ x=%d; y=%d }" x y
+
+let stringify_vec2 { vx; vy } =
+  Printf.sprintf "{ vx=%.2f; vy=%.2f }" vx vy
+
+let inspect_shape shape =
+  let repr = Obj.repr shape in
+  let layout =
+    if Obj.is_int repr then Printf.sprintf "immediate %d" (Obj.obj repr : int)
+    else Printf.sprintf "block tag=%d size=%d" (Obj.tag repr) (Obj.size repr)
+  in
+  let contents = match shape with
+    | Circle r -> Printf.sprintf "Circle(%.2f)" r
+    | Rect (w, h) -> Printf.sprintf "Rect(%.2f, %.2f)" w h
+    | Unit -> "Unit"
+  in
+  layout ^ ": " ^ contents
+
 let stringify_variant = function
   | RotateLeft -> "RotateLeft"
   | RotateRight -> "RotateRight"
@@ -64,6 +142,26 @@ let stringify_polymorphic_variant = function
   | `Multiple (n, s) -> Printf.sprintf "Multiple(%d, %s)" n s
   | `Unit -> "Unit"
 
+let stringify_poly_shape (shape : poly_shape) =
+  match shape with
+  | `Circle r -> Printf.sprintf "Circle(%.2f)" r
+  | `Rect (w, h) -> Printf.sprintf "Rect(%.2f, %.2f)" w h
+  | `Unit -> "Unit"
+
+let stringify_inventory_item item =
+  Printf.sprintf "%s x%d @ %.2f [%s] %s" item.name item.count item.price
+    (String.concat "; " item.tags)
+    (match item.discount with
+     | None -> "no discount"
+     | Some d -> Printf.sprintf "%.0f%% off" (d *. 100.))
+
+let stringify_inventory_event (event : inventory_event) =
+  match event with
+  | `Started -> "Started"
+  | `Moved (x, y) -> Printf.sprintf "Moved(%d, %d)" x y
+  | `Renamed name -> Printf.sprintf "Renamed(%s)" name
+  | `Resized (w, h) -> Printf.sprintf "Resized(%dx%d)" w h
+
 let raises_message_exception msg = failwith msg
 
 let raises_nonblock_exception () = raise Not_found
@@ -84,6 +182,58 @@ let double_u16_array arr =
     Array1.(set arr i ((get arr i) * 2))
   done
 
+let array2_dims arr =
+  let open Bigarray in
+  (Array2.dim1 arr, Array2.dim2 arr)
+let char_code c = Char.code c
+
+let create_float_array n =
+  Bigarray.(Array1.create float64 c_layout n)
+
+let stringify_int_lists l =
+  let stringify_ints l = "[" ^ String.concat ", " (List.map string_of_int l) ^ "]" in
+  String.concat "; " (List.map stringify_ints l)
+
+let rec stringify_json = function
+  | Null -> "null"
+  | Bool b -> string_of_bool b
+  | Int n -> string_of_int n
+  | Float f -> Printf.sprintf "%g" f
+  | String s -> Printf.sprintf "%S" s
+  | List l -> "[" ^ String.concat ", " (List.map stringify_json l) ^ "]"
+  | Assoc members ->
+    let stringify_member (k, v) = Printf.sprintf "%S: %s" k (stringify_json v) in
+    "{" ^ String.concat ", " (List.map stringify_member members) ^ "}"
+
+let float_array_length arr = Array.length arr
+
+let float_array_to_list arr = Array.to_list arr
+
+let int_adder () = (+)
+
+let mul_add () = fun a b c -> a * b + c
+
+let failing_closure () = fun msg -> failwith msg
+
+let bytes_array_length arr = Bigarray.Array1.dim arr
+
+let bytes_array_get arr i = Bigarray.Array1.get arr i
+
+let int_array_get arr i = Array.get arr i
+
+let string_array_concat arr = String.concat "," (Array.to_list arr)
+
+let sum_float_array arr =
+  let open Bigarray in
+  let sum = ref 0. in
+  for i = 0 to pred (Array1.dim arr) do
+    sum := !sum +. Array1.get arr i
+  done;
+  !sum
+
+let stringify_tuple14 (i1, s1, b1, i2, s2, b2, i3, s3, b3, i4, s4, b4, i5, s5) =
+  Printf.sprintf "(%d, %s, %b, %d, %s, %b, %d, %s, %b, %d, %s, %b, %d, %s)"
+    i1 s1 b1 i2 s2 b2 i3 s3 b3 i4 s4 b4 i5 s5
 
 let () =
   Callback.register "increment_bytes" increment_bytes;
@@ -94,12 +244,42 @@ let () =
   Callback.register "make_some" make_some;
   Callback.register "make_ok" make_ok;
   Callback.register "make_error" make_error;
+  Callback.register "sys_argv" sys_argv;
+  Callback.register "make_counters" make_counters;
+  Callback.register "incr_counters" incr_counters;
+  Callback.register "counter_values" counter_values;
+  Callback.register "ocaml_major_version" ocaml_major_version;
+  Callback.register "make_exn_error" make_exn_error;
+  Callback.register "make_not_found_error" make_not_found_error;
   Callback.register "stringify_record" stringify_record;
   Callback.register "stringify_variant" stringify_variant;
+  Callback.register "stringify_point" stringify_point;
+  Callback.register "stringify_vec2" stringify_vec2;
+  Callback.register "inspect_shape" inspect_shape;
   Callback.register "stringify_polymorphic_variant" stringify_polymorphic_variant;
+  Callback.register "stringify_poly_shape" stringify_poly_shape;
+  Callback.register "stringify_inventory_item" stringify_inventory_item;
+  Callback.register "stringify_inventory_event" stringify_inventory_event;
   Callback.register "raises_message_exception" raises_message_exception;
   Callback.register "raises_nonmessage_exception" raises_nonmessage_exception;
   Callback.register "raises_nonblock_exception" raises_nonblock_exception;
   Callback.register "gc_compact" Gc.compact;
+  Callback.register "gc_minor" Gc.minor;
   Callback.register "reverse_list_and_compact" reverse_list_and_compact;
   Callback.register "double_u16_array" double_u16_array;
+  Callback.register "array2_dims" array2_dims;
+  Callback.register "char_code" char_code;
+  Callback.register "create_float_array" create_float_array;
+  Callback.register "sum_float_array" sum_float_array;
+  Callback.register "bytes_array_length" bytes_array_length;
+  Callback.register "bytes_array_get" bytes_array_get;
+  Callback.register "int_array_get" int_array_get;
+  Callback.register "string_array_concat" string_array_concat;
+  Callback.register "float_array_length" float_array_length;
+  Callback.register "stringify_json" stringify_json;
+  Callback.register "stringify_int_lists" stringify_int_lists;
+  Callback.register "float_array_to_list" float_array_to_list;
+  Callback.register "stringify_tuple14" stringify_tuple14;
+  Callback.register "int_adder" int_adder;
+  Callback.register "mul_add" mul_add;
+  Callback.register "failing_closure" failing_closure;
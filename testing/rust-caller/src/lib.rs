@@ -4,13 +4,49 @@
 extern crate ocaml_interop;
 
 #[cfg(test)]
-use ocaml_interop::cons;
+use ocaml_interop::{
+    alloc_array, alloc_bigarray1_from_vec, alloc_bytes_chunked, alloc_custom_box, blit_str_into,
+    cons, deep_copy, store_array_element, Custom, FromOCaml, OCamlStringBuilder, RecordBuilder,
+};
+#[cfg(test)]
+use ocaml_interop::{
+    display_to_ocaml, flatten_to_ocaml, fold_into_ocaml, instant_to_ocaml, iter_to_tuple3,
+    ocaml_list_from_forward_iter, ocaml_list_from_forward_iter_with_options, ocaml_list_from_iter,
+    ocaml_list_from_iter_with_options, ocaml_list_of_keys, ocaml_list_of_values, ConversionOptions,
+    ListBuilder, TryToOCaml,
+};
+#[cfg(test)]
+use ocaml_interop::{to_ocaml_via_serde, OCamlSerializeError};
 use ocaml_interop::{OCaml, OCamlBytes, OCamlRuntime, ToOCaml};
 #[cfg(test)]
-use std::borrow::Borrow;
+use std::borrow::{Borrow, Cow};
 
 #[cfg(test)]
-use ocaml_interop::{bigarray, BoxRoot};
+use ocaml_interop::{
+    bigarray, BoxRoot, BoxRootPool, OCamlArray, OCamlChar, OCamlControlFlow, OCamlDispatcher,
+    OCamlException, OCamlFloat, OCamlFloatArray, OCamlInt, OCamlInt32, OCamlInt64, OCamlList,
+    OCamlNativeInt,
+};
+#[cfg(test)]
+use std::collections::{BTreeMap, BTreeSet, HashMap, LinkedList, VecDeque};
+#[cfg(test)]
+use std::ffi::CStr;
+#[cfg(test)]
+use std::hash::{BuildHasherDefault, Hasher};
+#[cfg(test)]
+use std::net::Ipv4Addr;
+#[cfg(test)]
+use std::num::{NonZeroI64, NonZeroU32, NonZeroU64, Saturating, Wrapping};
+#[cfg(test)]
+use std::ops::ControlFlow;
+#[cfg(test)]
+use std::path::PathBuf;
+#[cfg(test)]
+use std::rc::Rc;
+#[cfg(test)]
+use std::sync::Arc;
+#[cfg(test)]
+use std::time::{Duration, UNIX_EPOCH};
 
 mod ocaml {
     use ocaml_interop::*;
@@ -36,6 +72,85 @@ mod ocaml {
         Multiple(i64, String),
     }
 
+    #[cfg(test)]
+    #[derive(ToOCaml)]
+    pub struct Point {
+        #[ocaml(as_ = "OCamlInt")]
+        pub x: i64,
+        #[ocaml(as_ = "OCamlInt")]
+        pub y: i64,
+    }
+
+    #[cfg(test)]
+    #[derive(ToOCaml, serde::Serialize)]
+    pub struct Vec2 {
+        #[ocaml(as_ = "OCamlFloat")]
+        pub vx: f64,
+        #[ocaml(as_ = "OCamlFloat")]
+        pub vy: f64,
+    }
+
+    #[cfg(test)]
+    #[derive(ToOCaml)]
+    #[ocaml(transparent)]
+    pub struct UserId(#[ocaml(as_ = "OCamlInt")] pub i64);
+
+    #[cfg(test)]
+    #[derive(ToOCaml)]
+    #[ocaml(as_ = "(OCamlInt, ())")]
+    pub struct Tagged<X> {
+        #[ocaml(as_ = "OCamlInt")]
+        pub id: i64,
+        #[ocaml(as_ = "()")]
+        pub marker: std::marker::PhantomData<X>,
+    }
+
+    #[cfg(test)]
+    #[derive(ToOCaml)]
+    pub enum Shape {
+        Circle(#[ocaml(as_ = "OCamlFloat")] f64),
+        Rect(
+            #[ocaml(as_ = "OCamlFloat")] f64,
+            #[ocaml(as_ = "OCamlFloat")] f64,
+        ),
+        Unit,
+    }
+
+    #[cfg(test)]
+    #[derive(ToOCaml)]
+    #[ocaml(polymorphic_variant)]
+    pub enum PolyShape {
+        Circle(#[ocaml(as_ = "OCamlFloat")] f64),
+        Rect(
+            #[ocaml(as_ = "OCamlFloat")] f64,
+            #[ocaml(as_ = "OCamlFloat")] f64,
+        ),
+        Unit,
+    }
+
+    // An OCaml `int ref`
+    #[cfg(test)]
+    pub struct IntRef {}
+
+    #[cfg(test)]
+    #[derive(serde::Serialize)]
+    pub struct InventoryItem {
+        pub name: String,
+        pub count: u32,
+        pub price: f64,
+        pub tags: Vec<String>,
+        pub discount: Option<f64>,
+    }
+
+    #[cfg(test)]
+    #[derive(serde::Serialize)]
+    pub enum InventoryEvent {
+        Started,
+        Moved(i64, i64),
+        Renamed(String),
+        Resized { width: i64, height: i64 },
+    }
+
     impl_to_ocaml_record! {
         TestRecord {
             i: OCamlInt,
@@ -71,6 +186,9 @@ mod ocaml {
         pub fn make_some(value: String) -> Option<String>;
         pub fn make_ok(value: OCamlInt) -> Result<OCamlInt, String>;
         pub fn make_error(value: String) -> Result<OCamlInt, String>;
+        pub fn make_exn_error(message: String) -> Result<OCamlInt, OCamlExn>;
+        pub fn ocaml_major_version(unit: ()) -> OCamlInt;
+        pub fn make_not_found_error(unit: ()) -> Result<OCamlInt, OCamlExn>;
         pub fn stringify_record(record: TestRecord) -> String;
         pub fn stringify_variant(variant: Movement) -> String;
         pub fn stringify_polymorphic_variant(pvariant: PolymorphicEnum) -> String;
@@ -78,9 +196,45 @@ mod ocaml {
         pub fn raises_nonmessage_exception(unit: ());
         pub fn raises_nonblock_exception(unit: ());
         pub fn gc_compact(unit: ());
+        pub fn gc_minor(unit: ());
         pub fn reverse_list_and_compact(list: OCamlList<DynBox<u16>>)
             -> OCamlList<DynBox<u16>>;
         pub fn double_u16_array(array: bigarray::Array1<u16>);
+        pub fn array2_dims(array: bigarray::Array2<f64>) -> (OCamlInt, OCamlInt);
+        pub fn create_float_array(len: OCamlInt) -> bigarray::Array1<f64>;
+        pub fn sum_float_array(array: bigarray::Array1<f64>) -> OCamlFloat;
+        pub fn bytes_array_length(array: bigarray::Array1<u8>) -> OCamlInt;
+        pub fn bytes_array_get(array: bigarray::Array1<u8>, index: OCamlInt) -> OCamlInt;
+        pub fn int_array_get(array: OCamlArray<OCamlInt>, index: OCamlInt) -> OCamlInt;
+        pub fn string_array_concat(array: OCamlArray<String>) -> String;
+        pub fn float_array_length(array: OCamlFloatArray) -> OCamlInt;
+        pub fn stringify_int_lists(lists: OCamlList<OCamlList<OCamlInt>>) -> String;
+        pub fn stringify_json(json: OCamlJson) -> String;
+        pub fn float_array_to_list(array: OCamlFloatArray) -> OCamlList<OCamlFloat>;
+        pub fn char_code(c: OCamlChar) -> OCamlInt;
+        pub fn int_adder(unit: ()) -> fn(OCamlInt, OCamlInt) -> OCamlInt;
+        pub fn mul_add(unit: ()) -> fn(OCamlInt, OCamlInt, OCamlInt) -> OCamlInt;
+        pub fn failing_closure(unit: ()) -> fn(String) -> OCamlInt;
+        pub fn stringify_tuple14(
+            tuple: (
+                OCamlInt, String, bool, OCamlInt, String, bool, OCamlInt,
+                String, bool, OCamlInt, String, bool, OCamlInt, String,
+            )
+        ) -> String;
+    }
+
+    // Functions that take or return the types that are only used by tests
+    #[cfg(test)]
+    ocaml! {
+        pub fn make_counters(n: OCamlInt) -> OCamlList<(IntRef, String)>;
+        pub fn incr_counters(counters: OCamlList<(IntRef, String)>);
+        pub fn counter_values(counters: OCamlList<(IntRef, String)>) -> OCamlList<OCamlInt>;
+        pub fn stringify_poly_shape(shape: PolyShape) -> String;
+        pub fn stringify_inventory_item(item: InventoryItem) -> String;
+        pub fn stringify_inventory_event(event: InventoryEvent) -> String;
+        pub fn stringify_point(point: Point) -> String;
+        pub fn stringify_vec2(vec: Vec2) -> String;
+        pub fn inspect_shape(shape: Shape) -> String;
     }
 }
 
@@ -140,7 +294,10 @@ pub fn verify_variant_test(cr: &mut OCamlRuntime, variant: ocaml::Movement) -> S
     result.to_rust(cr)
 }
 
-pub fn verify_polymorphic_variant_test(cr: &mut OCamlRuntime, variant: ocaml::PolymorphicEnum) -> String {
+pub fn verify_polymorphic_variant_test(
+    cr: &mut OCamlRuntime,
+    variant: ocaml::PolymorphicEnum,
+) -> String {
     let ocaml_variant = variant.to_boxroot(cr);
     let result = ocaml::stringify_polymorphic_variant(cr, &ocaml_variant);
     result.to_rust(cr)
@@ -283,7 +440,10 @@ fn test_polymorphic_variant_conversion() {
         "Single(10.00)".to_owned()
     );
     assert_eq!(
-        verify_polymorphic_variant_test(&mut cr, ocaml::PolymorphicEnum::Multiple(10, "text".to_string())),
+        verify_polymorphic_variant_test(
+            &mut cr,
+            ocaml::PolymorphicEnum::Multiple(10, "text".to_string())
+        ),
         "Multiple(10, text)".to_owned()
     );
 }
@@ -341,6 +501,26 @@ fn test_exception_handling_without_message() {
     );
 }
 
+#[test]
+#[serial]
+fn test_exn_result_from_ocaml() {
+    OCamlRuntime::init_persistent();
+    let cr = unsafe { OCamlRuntime::recover_handle() };
+
+    let message = "boom".to_boxroot(cr);
+    let result: Result<i64, OCamlException> = ocaml::make_exn_error(cr, &message).to_rust(cr);
+    let error = result.unwrap_err();
+    assert_eq!(error.name(), "Failure");
+    assert_eq!(error.message(), Some("boom".to_owned()));
+    assert_eq!(error.to_string(), "Failure(\"boom\")");
+
+    let result: Result<i64, OCamlException> =
+        ocaml::make_not_found_error(cr, &OCaml::unit()).to_rust(cr);
+    let error = result.unwrap_err();
+    assert_eq!(error.name(), "Not_found");
+    assert_eq!(error.message(), None);
+}
+
 #[test]
 #[serial]
 fn test_exception_handling_nonblock_exception() {
@@ -381,3 +561,1991 @@ fn test_dynbox() {
     ocaml::gc_compact(&mut cr, OCaml::unit().as_ref());
     assert_eq!(vec2, vec![3, 2, 1, 0]);
 }
+
+#[test]
+#[serial]
+fn test_ocaml_list_iter_is_lazy() {
+    OCamlRuntime::init_persistent();
+    let cr = unsafe { OCamlRuntime::recover_handle() };
+
+    let values: Vec<i64> = (1..=1000).collect();
+    let list: BoxRoot<OCamlList<OCamlInt>> = values.to_boxroot(cr);
+    let list = cr.get(&list);
+
+    let first_three: i64 = list.iter().take(3).map(|n| n.to_i64()).sum();
+    assert_eq!(first_three, 6);
+    assert_eq!(list.iter().count(), 1000);
+    assert_eq!(list.iter().fold(0, |acc, n| acc + n.to_i64()), 500500);
+}
+
+#[test]
+#[serial]
+fn test_hashmap_to_assoc_list() {
+    OCamlRuntime::init_persistent();
+    let cr = unsafe { OCamlRuntime::recover_handle() };
+
+    let mut map = HashMap::new();
+    map.insert(1i64, "one".to_owned());
+    map.insert(2, "two".to_owned());
+    map.insert(3, "three".to_owned());
+
+    let ocaml_list: BoxRoot<OCamlList<(OCamlInt, String)>> = map.to_boxroot(cr);
+    let entries: Vec<(i64, String)> = ocaml_list.to_rust(cr);
+    assert_eq!(entries.len(), map.len());
+    for (key, value) in &map {
+        assert!(entries.contains(&(*key, value.clone())));
+    }
+}
+
+// Minimal FNV-1a hasher, standing in for hashers like `FxHasher`
+#[cfg(test)]
+struct FnvHasher(u64);
+
+#[cfg(test)]
+impl Default for FnvHasher {
+    fn default() -> Self {
+        FnvHasher(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+#[cfg(test)]
+impl Hasher for FnvHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 = (self.0 ^ u64::from(*byte)).wrapping_mul(0x100_0000_01b3);
+        }
+    }
+}
+
+#[test]
+#[serial]
+fn test_hashmap_with_custom_hasher_to_assoc_list() {
+    OCamlRuntime::init_persistent();
+    let cr = unsafe { OCamlRuntime::recover_handle() };
+
+    let mut map: HashMap<i64, String, BuildHasherDefault<FnvHasher>> = HashMap::default();
+    map.insert(1, "one".to_owned());
+    map.insert(2, "two".to_owned());
+
+    let ocaml_list: BoxRoot<OCamlList<(OCamlInt, String)>> = (&map).to_boxroot(cr);
+    let mut entries: Vec<(i64, String)> = ocaml_list.to_rust(cr);
+    entries.sort();
+    assert_eq!(entries, vec![(1, "one".to_owned()), (2, "two".to_owned())]);
+
+    let keys: BoxRoot<OCamlList<OCamlInt>> = BoxRoot::new(ocaml_list_of_keys(cr, &map));
+    let mut keys: Vec<i64> = keys.to_rust(cr);
+    keys.sort();
+    assert_eq!(keys, vec![1, 2]);
+}
+
+#[test]
+#[serial]
+fn test_btreemap_to_sorted_assoc_list() {
+    OCamlRuntime::init_persistent();
+    let cr = unsafe { OCamlRuntime::recover_handle() };
+
+    let mut map = BTreeMap::new();
+    map.insert(30i64, "thirty".to_owned());
+    map.insert(10, "ten".to_owned());
+    map.insert(20, "twenty".to_owned());
+
+    let ocaml_list: BoxRoot<OCamlList<(OCamlInt, String)>> = map.to_boxroot(cr);
+    let entries: Vec<(i64, String)> = ocaml_list.to_rust(cr);
+    assert_eq!(
+        entries,
+        vec![
+            (10, "ten".to_owned()),
+            (20, "twenty".to_owned()),
+            (30, "thirty".to_owned()),
+        ]
+    );
+}
+
+#[test]
+#[serial]
+fn test_array_to_list() {
+    OCamlRuntime::init_persistent();
+    let cr = unsafe { OCamlRuntime::recover_handle() };
+
+    let ocaml_list: BoxRoot<OCamlList<OCamlInt>> = [1i64, 2, 3].to_boxroot(cr);
+    let elements: Vec<i64> = ocaml_list.to_rust(cr);
+    assert_eq!(elements, vec![1, 2, 3]);
+}
+
+#[test]
+#[serial]
+fn test_vecdeque_to_list() {
+    OCamlRuntime::init_persistent();
+    let cr = unsafe { OCamlRuntime::recover_handle() };
+
+    let mut queue = VecDeque::new();
+    queue.push_back(2i64);
+    queue.push_back(3);
+    queue.push_front(1);
+
+    let ocaml_list: BoxRoot<OCamlList<OCamlInt>> = queue.to_boxroot(cr);
+    let elements: Vec<i64> = ocaml_list.to_rust(cr);
+    let popped: Vec<i64> = std::iter::from_fn(|| queue.pop_front()).collect();
+    assert_eq!(elements, popped);
+}
+
+#[test]
+#[serial]
+fn test_bigarray_to_vec() {
+    OCamlRuntime::init_persistent();
+    let cr = unsafe { OCamlRuntime::recover_handle() };
+
+    let data = [0.5f64, 1.5, 2.5, 3.5];
+    let arr_ocaml: BoxRoot<bigarray::Array1<f64>> = data.as_slice().to_boxroot(cr);
+    let copy = cr.get(&arr_ocaml).to_vec();
+    assert_eq!(copy, data);
+}
+
+#[test]
+#[serial]
+fn test_bigarray2() {
+    OCamlRuntime::init_persistent();
+    let cr = unsafe { OCamlRuntime::recover_handle() };
+
+    let data = [1.0f64, 2.0, 3.0, 4.0, 5.0, 6.0];
+    let matrix = bigarray::Matrix::new(&data, 2, 3);
+    let arr_ocaml: BoxRoot<bigarray::Array2<f64>> = matrix.to_boxroot(cr);
+    let dims: (i64, i64) = ocaml::array2_dims(cr, &arr_ocaml).to_rust(cr);
+    assert_eq!(dims, (2, 3));
+    let arr = cr.get(&arr_ocaml);
+    let flat: &[f64] = arr.borrow();
+    assert_eq!(flat, data);
+}
+
+#[test]
+#[serial]
+fn test_char_conversion() {
+    OCamlRuntime::init_persistent();
+    let cr = unsafe { OCamlRuntime::recover_handle() };
+
+    let c: BoxRoot<OCamlChar> = 'A'.to_boxroot(cr);
+    let code: i64 = ocaml::char_code(cr, &c).to_rust(cr);
+    assert_eq!(code, 0x41);
+    assert!(OCaml::of_char('\u{e9}').is_ok());
+    assert!(OCaml::of_char('\u{20ac}').is_err());
+}
+
+#[test]
+#[serial]
+fn test_unsigned_int_conversion() {
+    OCamlRuntime::init_persistent();
+    let cr = unsafe { OCamlRuntime::recover_handle() };
+
+    let n: BoxRoot<OCamlInt> = u32::MAX.to_boxroot(cr);
+    assert_eq!(n.to_rust::<i64>(cr), u32::MAX as i64);
+    let n: BoxRoot<OCamlInt> = 255u8.to_boxroot(cr);
+    assert_eq!(n.to_rust::<i64>(cr), 255);
+
+    assert_eq!(OCaml::of_u64(1 << 61).unwrap().to_i64(), 1 << 61);
+    assert!(OCaml::of_u64(1 << 62).is_err());
+    assert!(OCaml::of_u64(u64::MAX).is_err());
+}
+
+#[test]
+#[serial]
+fn test_f32_to_float() {
+    OCamlRuntime::init_persistent();
+    let cr = unsafe { OCamlRuntime::recover_handle() };
+
+    let x: BoxRoot<OCamlFloat> = 1.5f32.to_boxroot(cr);
+    assert_eq!(x.to_rust::<f64>(cr), 1.5);
+    let x: BoxRoot<OCamlFloat> = (&1.5f32).to_boxroot(cr);
+    assert_eq!(x.to_rust::<f64>(cr), 1.5);
+}
+
+#[test]
+#[serial]
+fn test_nativeint_roundtrip() {
+    OCamlRuntime::init_persistent();
+    let cr = unsafe { OCamlRuntime::recover_handle() };
+
+    for &n in &[isize::MIN, isize::MAX] {
+        let x: BoxRoot<OCamlNativeInt> = n.to_boxroot(cr);
+        assert_eq!(x.to_rust::<isize>(cr), n);
+    }
+}
+
+#[test]
+#[serial]
+fn test_tuple14_conversion() {
+    OCamlRuntime::init_persistent();
+    let cr = unsafe { OCamlRuntime::recover_handle() };
+
+    let tuple = (
+        1i64,
+        "a".to_owned(),
+        true,
+        2i64,
+        "b".to_owned(),
+        false,
+        3i64,
+        "c".to_owned(),
+        true,
+        4i64,
+        "d".to_owned(),
+        false,
+        5i64,
+        "e".to_owned(),
+    );
+    let tuple = tuple.to_boxroot(cr);
+    let result: String = ocaml::stringify_tuple14(cr, &tuple).to_rust(cr);
+    assert_eq!(
+        result,
+        "(1, a, true, 2, b, false, 3, c, true, 4, d, false, 5, e)"
+    );
+}
+
+#[test]
+#[serial]
+fn test_list_from_iter() {
+    OCamlRuntime::init_persistent();
+    let cr = unsafe { OCamlRuntime::recover_handle() };
+
+    let list: BoxRoot<OCamlList<OCamlInt>> =
+        ocaml_list_from_iter(cr, vec![1i64, 2, 3].into_iter().rev()).root();
+    assert_eq!(list.to_rust::<Vec<i64>>(cr), vec![3, 2, 1]);
+
+    let iter = std::iter::once(1i64)
+        .chain(std::iter::once(2))
+        .map(|n| n * 10);
+    let list: BoxRoot<OCamlList<OCamlInt>> = ocaml_list_from_forward_iter(cr, iter).root();
+    assert_eq!(list.to_rust::<Vec<i64>>(cr), vec![10, 20]);
+}
+
+#[test]
+#[serial]
+fn test_derived_record_conversion() {
+    OCamlRuntime::init_persistent();
+    let cr = unsafe { OCamlRuntime::recover_handle() };
+
+    let point = ocaml::Point { x: 10, y: -20 }.to_boxroot(cr);
+    let result: String = ocaml::stringify_point(cr, &point).to_rust(cr);
+    assert_eq!(result, "{ x=10; y=-20 }");
+}
+
+#[test]
+#[serial]
+fn test_derived_float_record_conversion() {
+    OCamlRuntime::init_persistent();
+    let cr = unsafe { OCamlRuntime::recover_handle() };
+
+    let vec = ocaml::Vec2 { vx: 1.5, vy: -0.25 }.to_boxroot(cr);
+    let result: String = ocaml::stringify_vec2(cr, &vec).to_rust(cr);
+    assert_eq!(result, "{ vx=1.50; vy=-0.25 }");
+}
+
+#[test]
+#[serial]
+fn test_ocaml_record_macro() {
+    OCamlRuntime::init_persistent();
+    let cr = unsafe { OCamlRuntime::recover_handle() };
+
+    let y = -4i64;
+    let point: OCaml<ocaml::Point> = ocaml_interop::ocaml_record! {
+        cr, ocaml::Point {
+            x: OCamlInt = 3i64,
+            y: OCamlInt = y * 2,
+        }
+    };
+    let x: i64 = unsafe { point.field::<OCamlInt>(0) }.to_rust();
+    let y: i64 = unsafe { point.field::<OCamlInt>(1) }.to_rust();
+    assert_eq!((x, y), (3, -8));
+
+    let point = point.root();
+    let result: String = ocaml::stringify_point(cr, &point).to_rust(cr);
+    assert_eq!(result, "{ x=3; y=-8 }");
+}
+
+#[test]
+#[serial]
+fn test_ocaml_record_macro_with_float_fields() {
+    OCamlRuntime::init_persistent();
+    let cr = unsafe { OCamlRuntime::recover_handle() };
+
+    let vy = 0.5f64;
+    let vec: BoxRoot<ocaml::Vec2> = ocaml_interop::ocaml_record! {
+        cr, ocaml::Vec2 {
+            vx: OCamlFloat = 2.0f64,
+            vy: OCamlFloat = vy * 3.0,
+        }
+    }
+    .root();
+    let result: String = ocaml::stringify_vec2(cr, &vec).to_rust(cr);
+    assert_eq!(result, "{ vx=2.00; vy=1.50 }");
+}
+
+#[test]
+#[serial]
+fn test_derived_variant_conversion() {
+    OCamlRuntime::init_persistent();
+    let cr = unsafe { OCamlRuntime::recover_handle() };
+
+    let shape = ocaml::Shape::Circle(1.5).to_boxroot(cr);
+    let result: String = ocaml::inspect_shape(cr, &shape).to_rust(cr);
+    assert_eq!(result, "block tag=0 size=1: Circle(1.50)");
+
+    let shape = ocaml::Shape::Rect(2.0, 3.0).to_boxroot(cr);
+    let result: String = ocaml::inspect_shape(cr, &shape).to_rust(cr);
+    assert_eq!(result, "block tag=1 size=2: Rect(2.00, 3.00)");
+
+    let shape = ocaml::Shape::Unit.to_boxroot(cr);
+    let result: String = ocaml::inspect_shape(cr, &shape).to_rust(cr);
+    assert_eq!(result, "immediate 0: Unit");
+}
+
+#[test]
+#[serial]
+fn test_derived_polymorphic_variant_conversion() {
+    OCamlRuntime::init_persistent();
+    let cr = unsafe { OCamlRuntime::recover_handle() };
+
+    let shape = ocaml::PolyShape::Circle(1.5).to_boxroot(cr);
+    let result: String = ocaml::stringify_poly_shape(cr, &shape).to_rust(cr);
+    assert_eq!(result, "Circle(1.50)");
+
+    let shape = ocaml::PolyShape::Rect(2.0, 3.0).to_boxroot(cr);
+    let result: String = ocaml::stringify_poly_shape(cr, &shape).to_rust(cr);
+    assert_eq!(result, "Rect(2.00, 3.00)");
+
+    let shape = ocaml::PolyShape::Unit.to_boxroot(cr);
+    let result: String = ocaml::stringify_poly_shape(cr, &shape).to_rust(cr);
+    assert_eq!(result, "Unit");
+}
+
+#[test]
+#[serial]
+fn test_cow_to_string() {
+    OCamlRuntime::init_persistent();
+    let cr = unsafe { OCamlRuntime::recover_handle() };
+
+    let borrowed: Cow<str> = Cow::Borrowed("text");
+    let owned: Cow<str> = Cow::Owned("text".to_owned());
+    let borrowed: BoxRoot<String> = borrowed.to_boxroot(cr);
+    let owned: BoxRoot<String> = owned.to_boxroot(cr);
+    assert_eq!(borrowed.to_rust::<String>(cr), "text");
+    assert_eq!(owned.to_rust::<String>(cr), "text");
+
+    let borrowed: Cow<[u8]> = Cow::Borrowed(b"bytes");
+    let owned: Cow<[u8]> = Cow::Owned(b"bytes".to_vec());
+    let borrowed: BoxRoot<OCamlBytes> = borrowed.to_boxroot(cr);
+    let owned: BoxRoot<OCamlBytes> = owned.to_boxroot(cr);
+    assert_eq!(borrowed.to_rust::<Vec<u8>>(cr), b"bytes");
+    assert_eq!(owned.to_rust::<Vec<u8>>(cr), b"bytes");
+}
+
+#[test]
+#[serial]
+fn test_rc_and_arc_to_ocaml() {
+    OCamlRuntime::init_persistent();
+    let cr = unsafe { OCamlRuntime::recover_handle() };
+
+    let rc = Rc::new("shared".to_owned());
+    let s: BoxRoot<String> = (&rc).to_boxroot(cr);
+    assert_eq!(s.to_rust::<String>(cr), "shared");
+    assert_eq!(Rc::strong_count(&rc), 1);
+
+    let arc = Arc::new(42i64);
+    let n: BoxRoot<OCamlInt> = (&arc).to_boxroot(cr);
+    assert_eq!(n.to_rust::<i64>(cr), 42);
+    assert_eq!(Arc::strong_count(&arc), 1);
+}
+
+#[test]
+#[serial]
+fn test_duration_to_ocaml() {
+    OCamlRuntime::init_persistent();
+    let cr = unsafe { OCamlRuntime::recover_handle() };
+
+    let duration = Duration::from_millis(1500);
+    let secs: BoxRoot<OCamlFloat> = duration.to_boxroot(cr);
+    assert_eq!(secs.to_rust::<f64>(cr), 1.5);
+    let nanos: BoxRoot<OCamlInt64> = duration.to_boxroot(cr);
+    assert_eq!(nanos.to_rust::<i64>(cr), 1_500_000_000);
+}
+
+#[test]
+#[serial]
+fn test_assoc_list_to_hashmap() {
+    OCamlRuntime::init_persistent();
+    let cr = unsafe { OCamlRuntime::recover_handle() };
+
+    let assoc = vec![
+        (1i64, "one".to_owned()),
+        (2, "two".to_owned()),
+        (1, "uno".to_owned()),
+    ];
+    let assoc: BoxRoot<OCamlList<(OCamlInt, String)>> = assoc.to_boxroot(cr);
+    let map: HashMap<i64, String> = assoc.to_rust(cr);
+    assert_eq!(map.len(), 2);
+    assert_eq!(map[&1], "uno");
+    assert_eq!(map[&2], "two");
+}
+
+#[test]
+#[serial]
+fn test_path_to_ocaml() {
+    OCamlRuntime::init_persistent();
+    let cr = unsafe { OCamlRuntime::recover_handle() };
+
+    let path = PathBuf::from("src/lib.rs");
+    let s: BoxRoot<String> = path.to_boxroot(cr);
+    assert_eq!(s.to_rust::<String>(cr), "src/lib.rs");
+    let s: BoxRoot<String> = path.as_path().try_to_boxroot(cr).unwrap();
+    assert_eq!(s.to_rust::<String>(cr), "src/lib.rs");
+}
+
+#[test]
+#[serial]
+fn test_borrow_string_bytes() {
+    OCamlRuntime::init_persistent();
+    let cr = unsafe { OCamlRuntime::recover_handle() };
+
+    let s: OCaml<String> = "payload".to_ocaml(cr);
+    assert_eq!(s.as_bytes(), b"payload");
+
+    let bytes: OCaml<OCamlBytes> = b"\x00\xffraw".as_ref().to_ocaml(cr);
+    assert_eq!(bytes.as_bytes(), b"\x00\xffraw");
+}
+
+#[test]
+#[serial]
+fn test_checked_utf8_string_from_ocaml() {
+    OCamlRuntime::init_persistent();
+    let cr = unsafe { OCamlRuntime::recover_handle() };
+
+    let valid: BoxRoot<String> = "h\u{e9}llo \u{1f42b}".to_boxroot(cr);
+    assert_eq!(
+        valid.try_to_rust::<String>(cr).unwrap(),
+        "h\u{e9}llo \u{1f42b}"
+    );
+
+    let invalid: BoxRoot<String> = b"ab\xff\xfecd".as_ref().to_boxroot(cr);
+    let error = invalid.try_to_rust::<String>(cr).unwrap_err();
+    assert_eq!(error.valid_up_to(), 2);
+    // The infallible conversion still succeeds, replacing the invalid bytes
+    assert_eq!(invalid.to_rust::<String>(cr), "ab\u{fffd}\u{fffd}cd");
+
+    let invalid: OCaml<OCamlBytes> = b"\xc3".as_ref().to_ocaml(cr);
+    assert!(invalid.try_to_rust::<String>().is_err());
+}
+
+#[test]
+#[serial]
+fn test_checked_int_conversion() {
+    OCamlRuntime::init_persistent();
+    let cr = unsafe { OCamlRuntime::recover_handle() };
+
+    let n: BoxRoot<OCamlInt> = (1i64 << 61).try_to_boxroot(cr).unwrap();
+    assert_eq!(n.to_rust::<i64>(cr), 1 << 61);
+    let n: Result<BoxRoot<OCamlInt>, _> = (1i64 << 62).try_to_boxroot(cr);
+    assert!(n.is_err());
+    let n: Result<BoxRoot<OCamlInt>, _> = (-(1i64 << 62) - 1).try_to_boxroot(cr);
+    assert!(n.is_err());
+    let n: Result<BoxRoot<OCamlInt>, _> = u64::MAX.try_to_boxroot(cr);
+    assert!(n.is_err());
+}
+
+#[test]
+#[serial]
+fn test_mixed_ownership_tuple_conversion() {
+    OCamlRuntime::init_persistent();
+    let cr = unsafe { OCamlRuntime::recover_handle() };
+
+    let cached = 42i64;
+    let tuple: BoxRoot<(String, OCamlInt)> = ("fresh".to_owned(), &cached).to_boxroot(cr);
+    assert_eq!(tuple.to_rust::<(String, i64)>(cr), ("fresh".to_owned(), 42));
+}
+
+#[test]
+#[serial]
+fn test_ipv4_to_ocaml() {
+    OCamlRuntime::init_persistent();
+    let cr = unsafe { OCamlRuntime::recover_handle() };
+
+    let addr = Ipv4Addr::new(127, 0, 0, 1);
+    let packed: BoxRoot<OCamlInt32> = addr.to_boxroot(cr);
+    assert_eq!(packed.to_rust::<i32>(cr), 0x7F000001);
+    let dotted: BoxRoot<String> = addr.to_boxroot(cr);
+    assert_eq!(dotted.to_rust::<String>(cr), "127.0.0.1");
+}
+
+#[test]
+#[serial]
+fn test_non_zero_to_ocaml() {
+    OCamlRuntime::init_persistent();
+    let cr = unsafe { OCamlRuntime::recover_handle() };
+
+    let id = NonZeroI64::new(42).unwrap();
+    let n: BoxRoot<OCamlInt64> = id.to_boxroot(cr);
+    assert_eq!(n.to_rust::<i64>(cr), 42);
+
+    let id = NonZeroU32::new(u32::MAX).unwrap();
+    let n: BoxRoot<OCamlInt> = id.to_boxroot(cr);
+    assert_eq!(n.to_rust::<i64>(cr), u32::MAX as i64);
+
+    let id = NonZeroU64::new(u64::MAX).unwrap();
+    let n: Result<BoxRoot<OCamlInt>, _> = id.try_to_boxroot(cr);
+    assert!(n.is_err());
+}
+
+#[test]
+#[serial]
+fn test_bigarray_as_mut_slice() {
+    OCamlRuntime::init_persistent();
+    let cr = unsafe { OCamlRuntime::recover_handle() };
+
+    let len = 4i64.to_boxroot(cr);
+    let arr_ocaml: BoxRoot<bigarray::Array1<f64>> = ocaml::create_float_array(cr, &len);
+    {
+        let mut arr = cr.get(&arr_ocaml);
+        let data = unsafe { arr.as_mut_slice(cr) };
+        for (i, x) in data.iter_mut().enumerate() {
+            *x = i as f64 + 0.5;
+        }
+    }
+    let sum: f64 = ocaml::sum_float_array(cr, &arr_ocaml).to_rust(cr);
+    assert_eq!(sum, 8.0);
+    assert_eq!(cr.get(&arr_ocaml).as_slice(), &[0.5, 1.5, 2.5, 3.5]);
+}
+
+#[test]
+#[serial]
+fn test_btreeset_to_sorted_list() {
+    OCamlRuntime::init_persistent();
+    let cr = unsafe { OCamlRuntime::recover_handle() };
+
+    let set: BTreeSet<i64> = [30, 10, 20, 10].iter().copied().collect();
+    let ocaml_list: BoxRoot<OCamlList<OCamlInt>> = set.to_boxroot(cr);
+    let elements: Vec<i64> = ocaml_list.to_rust(cr);
+    assert_eq!(elements, vec![10, 20, 30]);
+}
+
+#[test]
+#[serial]
+fn test_large_u8_bigarray() {
+    OCamlRuntime::init_persistent();
+    let cr = unsafe { OCamlRuntime::recover_handle() };
+
+    let data: Vec<u8> = (0..10 * 1024 * 1024).map(|i| i as u8).collect();
+    let arr_ocaml: BoxRoot<bigarray::Array1<u8>> = data.as_slice().to_boxroot(cr);
+    let len: i64 = ocaml::bytes_array_length(cr, &arr_ocaml).to_rust(cr);
+    assert_eq!(len, data.len() as i64);
+
+    let arr = cr.get(&arr_ocaml);
+    let borrowed: &[u8] = arr.borrow();
+    assert_eq!(borrowed, data.as_slice());
+}
+
+#[test]
+#[serial]
+fn test_list_to_array() {
+    OCamlRuntime::init_persistent();
+    let cr = unsafe { OCamlRuntime::recover_handle() };
+
+    let ocaml_list: BoxRoot<OCamlList<OCamlInt>> = vec![1i64, 2, 3].to_boxroot(cr);
+    let array: [i64; 3] = ocaml_list.to_rust(cr);
+    assert_eq!(array, [1, 2, 3]);
+}
+
+#[test]
+#[serial]
+#[should_panic(expected = "expected an OCaml list of length 3, got a list of length 2")]
+fn test_list_to_array_length_mismatch() {
+    OCamlRuntime::init_persistent();
+    let cr = unsafe { OCamlRuntime::recover_handle() };
+
+    let ocaml_list: BoxRoot<OCamlList<OCamlInt>> = vec![1i64, 2].to_boxroot(cr);
+    let _array: [i64; 3] = ocaml_list.to_rust(cr);
+}
+
+#[test]
+#[serial]
+fn test_mut_ref_to_ocaml() {
+    OCamlRuntime::init_persistent();
+    let cr = unsafe { OCamlRuntime::recover_handle() };
+
+    let mut words = vec!["a".to_owned(), "b".to_owned()];
+    for word in words.iter_mut() {
+        word.push('!');
+        let ocaml_word: BoxRoot<String> = ToOCaml::to_boxroot(&word, cr);
+        assert_eq!(ocaml_word.to_rust::<String>(cr), *word);
+    }
+}
+
+#[test]
+#[serial]
+fn test_float_array() {
+    OCamlRuntime::init_persistent();
+    let cr = unsafe { OCamlRuntime::recover_handle() };
+
+    let data = [0.5f64, -1.25, 3.0];
+    let arr_ocaml: BoxRoot<OCamlFloatArray> = data.as_slice().to_boxroot(cr);
+    let len: i64 = ocaml::float_array_length(cr, &arr_ocaml).to_rust(cr);
+    assert_eq!(len, 3);
+    let elements: Vec<f64> = ocaml::float_array_to_list(cr, &arr_ocaml).to_rust(cr);
+    assert_eq!(elements, data);
+
+    let arr = cr.get(&arr_ocaml);
+    let borrowed: &[f64] = arr.borrow();
+    assert_eq!(borrowed, data);
+}
+
+#[test]
+#[serial]
+fn test_vec_to_ocaml_array() {
+    OCamlRuntime::init_persistent();
+    let cr = unsafe { OCamlRuntime::recover_handle() };
+
+    let data: Vec<i64> = vec![10, 20, 30];
+    let array: BoxRoot<OCamlArray<OCamlInt>> = data.to_boxroot(cr);
+    for (index, expected) in data.iter().enumerate() {
+        let index = (index as i64).to_boxroot(cr);
+        let element: i64 = ocaml::int_array_get(cr, &array, &index).to_rust(cr);
+        assert_eq!(element, *expected);
+    }
+
+    let names: &[&str] = &["a", "b", "c"];
+    let array: BoxRoot<OCamlArray<String>> = names.to_boxroot(cr);
+    let joined: String = ocaml::string_array_concat(cr, &array).to_rust(cr);
+    assert_eq!(joined, "a,b,c");
+
+    let empty: Vec<String> = vec![];
+    let array: BoxRoot<OCamlArray<String>> = empty.to_boxroot(cr);
+    let joined: String = ocaml::string_array_concat(cr, &array).to_rust(cr);
+    assert_eq!(joined, "");
+
+    let array: BoxRoot<OCamlArray<String>> = BoxRoot::new(unsafe { alloc_array(cr, 2) });
+    for (index, name) in ["x", "y"].iter().enumerate() {
+        let name: BoxRoot<String> = name.to_boxroot(cr);
+        store_array_element(cr, &array, index, &name);
+    }
+    let joined: String = ocaml::string_array_concat(cr, &array).to_rust(cr);
+    assert_eq!(joined, "x,y");
+}
+
+#[test]
+#[serial]
+fn test_bytes_crate_buffers_to_ocaml() {
+    OCamlRuntime::init_persistent();
+    let cr = unsafe { OCamlRuntime::recover_handle() };
+
+    let buffer = bytes::Bytes::from_static(b"abc");
+    let ocaml_bytes: BoxRoot<OCamlBytes> = (&buffer).to_boxroot(cr);
+    assert_eq!(ocaml_bytes.to_rust::<Vec<u8>>(cr), b"abc");
+
+    let mut buffer = bytes::BytesMut::with_capacity(16);
+    buffer.extend_from_slice(b"\x00\xffdef");
+    let ocaml_bytes: BoxRoot<OCamlBytes> = buffer.to_boxroot(cr);
+    assert_eq!(ocaml_bytes.to_rust::<Vec<u8>>(cr), b"\x00\xffdef");
+}
+
+#[test]
+#[serial]
+fn test_fixed_size_float_array() {
+    OCamlRuntime::init_persistent();
+    let cr = unsafe { OCamlRuntime::recover_handle() };
+
+    let point = [1.0f64, 2.0, 3.0];
+    let arr_ocaml: BoxRoot<OCamlFloatArray> = point.to_boxroot(cr);
+    let len: i64 = ocaml::float_array_length(cr, &arr_ocaml).to_rust(cr);
+    assert_eq!(len, 3);
+    let elements: Vec<f64> = ocaml::float_array_to_list(cr, &arr_ocaml).to_rust(cr);
+    assert_eq!(elements, point);
+
+    let arr_ocaml: BoxRoot<OCamlFloatArray> = (&point).to_boxroot(cr);
+    let len: i64 = ocaml::float_array_length(cr, &arr_ocaml).to_rust(cr);
+    assert_eq!(len, 3);
+}
+
+#[test]
+#[serial]
+fn test_nested_vec_to_ocaml() {
+    OCamlRuntime::init_persistent();
+    let cr = unsafe { OCamlRuntime::recover_handle() };
+
+    let lists = vec![vec![1i64, 2], vec![3], vec![]];
+    let ocaml_lists: BoxRoot<OCamlList<OCamlList<OCamlInt>>> = lists.to_boxroot(cr);
+    let result: String = ocaml::stringify_int_lists(cr, &ocaml_lists).to_rust(cr);
+    assert_eq!(result, "[1, 2]; [3]; []");
+
+    let ocaml_lists: BoxRoot<OCamlList<OCamlList<OCamlInt>>> = (&lists).to_boxroot(cr);
+    let roundtrip: Vec<Vec<i64>> = ocaml_lists.to_rust(cr);
+    assert_eq!(roundtrip, lists);
+}
+
+#[test]
+#[serial]
+fn test_list_builder() {
+    OCamlRuntime::init_persistent();
+    let cr = unsafe { OCamlRuntime::recover_handle() };
+
+    let data: Vec<i64> = (0..100_000).collect();
+    let mut builder = ListBuilder::new();
+    for n in data.iter() {
+        builder.push(cr, n);
+    }
+    assert_eq!(builder.len(), data.len());
+    let built: BoxRoot<OCamlList<OCamlInt>> = builder.finish();
+    let converted: BoxRoot<OCamlList<OCamlInt>> = data.to_boxroot(cr);
+    assert_eq!(
+        built.to_rust::<Vec<i64>>(cr),
+        converted.to_rust::<Vec<i64>>(cr)
+    );
+
+    let empty: BoxRoot<OCamlList<String>> = ListBuilder::new().finish();
+    assert!(empty.to_rust::<Vec<String>>(cr).is_empty());
+}
+
+#[test]
+#[serial]
+fn test_nested_tuple_to_ocaml() {
+    OCamlRuntime::init_persistent();
+    let cr = unsafe { OCamlRuntime::recover_handle() };
+
+    let value = ocaml_interop::nested_tuple!(1i64, "two".to_owned(), true, 4.5f64);
+    let ocaml_value: BoxRoot<
+        ocaml_interop::nested_tuple!(type OCamlInt, String, bool, OCamlFloat),
+    > = value.to_boxroot(cr);
+
+    let ocaml_value = cr.get(&ocaml_value);
+    assert_eq!(ocaml_value.fst().to_rust::<i64>(), 1);
+    let rest = ocaml_value.snd();
+    assert_eq!(rest.fst().to_rust::<String>(), "two");
+    let rest = rest.snd();
+    assert!(rest.fst().to_rust::<bool>());
+    assert_eq!(rest.snd().to_rust::<f64>(), 4.5);
+}
+
+#[test]
+#[serial]
+fn test_json_to_ocaml() {
+    OCamlRuntime::init_persistent();
+    let cr = unsafe { OCamlRuntime::recover_handle() };
+
+    let mut stringify = |json: serde_json::Value| -> String {
+        let ocaml_json = json.to_boxroot(cr);
+        ocaml::stringify_json(cr, &ocaml_json).to_rust(cr)
+    };
+
+    assert_eq!(stringify(serde_json::json!(null)), "null");
+    assert_eq!(stringify(serde_json::json!(true)), "true");
+    assert_eq!(stringify(serde_json::json!(42)), "42");
+    assert_eq!(stringify(serde_json::json!(u64::MAX)), "1.84467e+19");
+    assert_eq!(stringify(serde_json::json!(1.5)), "1.5");
+    assert_eq!(stringify(serde_json::json!("text")), "\"text\"");
+    assert_eq!(
+        stringify(serde_json::json!([1, "two", null])),
+        "[1, \"two\", null]"
+    );
+    assert_eq!(
+        stringify(serde_json::json!({"a": [1, {"b": false}], "c": {}})),
+        "{\"a\": [1, {\"b\": false}], \"c\": {}}"
+    );
+}
+
+#[test]
+#[serial]
+fn test_cstr_to_ocaml() {
+    OCamlRuntime::init_persistent();
+    let cr = unsafe { OCamlRuntime::recover_handle() };
+
+    let c_str = CStr::from_bytes_with_nul(b"hello\0").unwrap();
+    let ocaml_string: BoxRoot<String> = c_str.to_boxroot(cr);
+    assert_eq!(cr.get(&ocaml_string).as_bytes().len(), 5);
+    assert_eq!(ocaml_string.to_rust::<String>(cr), "hello");
+
+    let ocaml_bytes: BoxRoot<OCamlBytes> = c_str.to_boxroot(cr);
+    assert_eq!(ocaml_bytes.to_rust::<Vec<u8>>(cr), b"hello");
+}
+
+#[test]
+#[serial]
+fn test_system_time_to_ocaml() {
+    OCamlRuntime::init_persistent();
+    let cr = unsafe { OCamlRuntime::recover_handle() };
+
+    let time = UNIX_EPOCH + Duration::from_secs(100);
+    let secs: BoxRoot<OCamlFloat> = time.to_boxroot(cr);
+    assert_eq!(secs.to_rust::<f64>(cr), 100.0);
+    let nanos: BoxRoot<OCamlInt64> = time.to_boxroot(cr);
+    assert_eq!(nanos.to_rust::<i64>(cr), 100_000_000_000);
+
+    let before_epoch = UNIX_EPOCH - Duration::from_millis(1500);
+    let secs: BoxRoot<OCamlFloat> = before_epoch.to_boxroot(cr);
+    assert_eq!(secs.to_rust::<f64>(cr), -1.5);
+}
+
+#[test]
+#[serial]
+fn test_chrono_date_time_to_ocaml() {
+    use chrono::{TimeZone, Utc};
+
+    OCamlRuntime::init_persistent();
+    let cr = unsafe { OCamlRuntime::recover_handle() };
+
+    let time = Utc.with_ymd_and_hms(2021, 1, 1, 0, 0, 0).unwrap();
+    let secs: BoxRoot<OCamlFloat> = time.to_boxroot(cr);
+    assert_eq!(secs.to_rust::<f64>(cr), 1_609_459_200.0);
+    let nanos: BoxRoot<OCamlInt64> = time.to_boxroot(cr);
+    assert_eq!(nanos.to_rust::<i64>(cr), 1_609_459_200_000_000_000);
+
+    let time = time + chrono::Duration::milliseconds(250);
+    let secs: BoxRoot<OCamlFloat> = time.to_boxroot(cr);
+    assert_eq!(secs.to_rust::<f64>(cr), 1_609_459_200.25);
+
+    let before_epoch = Utc.timestamp_opt(0, 0).unwrap() - chrono::Duration::milliseconds(1500);
+    let secs: BoxRoot<OCamlFloat> = before_epoch.to_boxroot(cr);
+    assert_eq!(secs.to_rust::<f64>(cr), -1.5);
+    let nanos: BoxRoot<OCamlInt64> = before_epoch.to_boxroot(cr);
+    assert_eq!(nanos.to_rust::<i64>(cr), -1_500_000_000);
+}
+
+#[test]
+#[serial]
+fn test_alloc_bytes_chunked() {
+    OCamlRuntime::init_persistent();
+    let cr = unsafe { OCamlRuntime::recover_handle() };
+
+    let chunks: Vec<Vec<u8>> = alloc_bytes_chunked(cr, b"abcdef", 3).to_rust();
+    assert_eq!(chunks, vec![b"abc".to_vec(), b"def".to_vec()]);
+
+    let chunks: Vec<Vec<u8>> = alloc_bytes_chunked(cr, b"abcdefg", 3).to_rust();
+    assert_eq!(
+        chunks,
+        vec![b"abc".to_vec(), b"def".to_vec(), b"g".to_vec()]
+    );
+
+    let chunks: Vec<Vec<u8>> = alloc_bytes_chunked(cr, b"", 3).to_rust();
+    assert!(chunks.is_empty());
+}
+
+#[test]
+#[serial]
+fn test_ranges_to_ocaml() {
+    OCamlRuntime::init_persistent();
+    let cr = unsafe { OCamlRuntime::recover_handle() };
+
+    let bounds: BoxRoot<(OCamlInt, OCamlInt)> = (2i64..5).to_boxroot(cr);
+    assert_eq!(bounds.to_rust::<(i64, i64)>(cr), (2, 5));
+
+    let bounds: BoxRoot<(OCamlInt, OCamlInt)> = (2i64..=5).to_boxroot(cr);
+    assert_eq!(bounds.to_rust::<(i64, i64)>(cr), (2, 5));
+}
+
+#[test]
+#[serial]
+fn test_boxroot_pool() {
+    OCamlRuntime::init_persistent();
+    let cr = unsafe { OCamlRuntime::recover_handle() };
+
+    let pool: BoxRootPool<String> = BoxRootPool::new(cr, 2);
+    let words = ["a", "b", "c"];
+    let roots: Vec<_> = words
+        .iter()
+        .map(|word| pool.root(word.to_ocaml(cr)))
+        .collect();
+    assert_eq!(pool.available(), 0);
+
+    ocaml::gc_minor(cr, &OCaml::unit());
+    let rooted: Vec<String> = roots.iter().map(|root| root.to_rust(cr)).collect();
+    assert_eq!(rooted, words);
+
+    drop(roots);
+    assert_eq!(pool.available(), 3);
+}
+
+#[test]
+#[serial]
+fn test_option_ref_to_ocaml() {
+    OCamlRuntime::init_persistent();
+    let cr = unsafe { OCamlRuntime::recover_handle() };
+
+    let mut map = HashMap::new();
+    map.insert("one".to_owned(), 1i64);
+
+    let found: BoxRoot<Option<OCamlInt>> = map.get("one").to_boxroot(cr);
+    assert_eq!(found.to_rust::<Option<i64>>(cr), Some(1));
+    let missing: BoxRoot<Option<OCamlInt>> = map.get("two").to_boxroot(cr);
+    assert_eq!(missing.to_rust::<Option<i64>>(cr), None);
+}
+
+#[test]
+#[serial]
+fn test_small_signed_ints_to_ocaml() {
+    OCamlRuntime::init_persistent();
+    let cr = unsafe { OCamlRuntime::recover_handle() };
+
+    let n: BoxRoot<OCamlInt> = i8::MIN.to_boxroot(cr);
+    assert_eq!(n.to_rust::<i64>(cr), i8::MIN as i64);
+    let n: BoxRoot<OCamlInt> = (&i16::MAX).to_boxroot(cr);
+    assert_eq!(n.to_rust::<i64>(cr), i16::MAX as i64);
+}
+
+#[test]
+#[serial]
+fn test_record_builder() {
+    OCamlRuntime::init_persistent();
+    let cr = unsafe { OCamlRuntime::recover_handle() };
+
+    let mut builder: RecordBuilder<(OCamlInt, String, OCamlFloat)> = RecordBuilder::new(cr, 3);
+    builder.set_field::<OCamlInt, _>(0, 7i64);
+    builder.set_field::<OCamlFloat, _>(2, 2.5f64);
+    builder.set_field::<String, _>(1, "seven");
+    let record: (i64, String, f64) = builder.build().to_rust();
+    assert_eq!(record, (7, "seven".to_owned(), 2.5));
+}
+
+#[test]
+#[serial]
+fn test_char_to_ocaml_string() {
+    OCamlRuntime::init_persistent();
+    let cr = unsafe { OCamlRuntime::recover_handle() };
+
+    let s: BoxRoot<String> = '\u{e9}'.to_boxroot(cr);
+    assert_eq!(cr.get(&s).as_bytes(), &[0xc3, 0xa9]);
+    assert_eq!(s.to_rust::<String>(cr), "\u{e9}");
+}
+
+#[test]
+#[serial]
+fn test_tuple_projection() {
+    OCamlRuntime::init_persistent();
+    let cr = unsafe { OCamlRuntime::recover_handle() };
+
+    let tuple: BoxRoot<(OCamlInt, String, bool)> = (5i64, "five".to_owned(), true).to_boxroot(cr);
+    let tuple = cr.get(&tuple);
+    assert_eq!(tuple.fst().to_rust::<i64>(), 5);
+    assert_eq!(tuple.snd().as_str(), "five");
+    assert!(tuple.tuple_3().to_rust::<bool>());
+}
+
+#[test]
+#[serial]
+fn test_map_keys_and_values_to_ocaml() {
+    OCamlRuntime::init_persistent();
+    let cr = unsafe { OCamlRuntime::recover_handle() };
+
+    let mut map = HashMap::new();
+    map.insert("one".to_owned(), 1i64);
+    map.insert("two".to_owned(), 2);
+    map.insert("three".to_owned(), 3);
+
+    let mut values: Vec<i64> = ocaml_list_of_values::<_, _, OCamlInt>(cr, &map).to_rust();
+    values.sort_unstable();
+    assert_eq!(values, vec![1, 2, 3]);
+
+    let keys: Vec<String> = ocaml_list_of_keys::<_, _, String>(cr, &map).to_rust();
+    assert_eq!(keys.len(), 3);
+    assert!(keys.iter().all(|key| map.contains_key(key)));
+}
+
+#[cfg(test)]
+struct ReentrantConversion;
+
+#[cfg(test)]
+unsafe impl FromOCaml<OCamlInt> for ReentrantConversion {
+    fn from_ocaml(_v: OCaml<OCamlInt>) -> Self {
+        let cr = unsafe { OCamlRuntime::recover_handle() };
+        let _allocated: BoxRoot<String> = "allocated".to_boxroot(cr);
+        ReentrantConversion
+    }
+}
+
+#[test]
+#[serial]
+#[should_panic(expected = "the OCaml runtime handle was recovered during the conversion")]
+fn test_reentrant_conversion_is_detected() {
+    OCamlRuntime::init_persistent();
+    let cr = unsafe { OCamlRuntime::recover_handle() };
+
+    let n: BoxRoot<OCamlInt> = 1i64.to_boxroot(cr);
+    let _converted: ReentrantConversion = n.to_rust(cr);
+}
+
+#[test]
+#[serial]
+fn test_string_lists_to_ocaml() {
+    OCamlRuntime::init_persistent();
+    let cr = unsafe { OCamlRuntime::recover_handle() };
+
+    let strings = vec!["a".to_string(), "b".to_string()];
+    let ocaml_list: BoxRoot<OCamlList<String>> = strings.to_boxroot(cr);
+    assert_eq!(ocaml_list.to_rust::<Vec<String>>(cr), strings);
+    let ocaml_list: BoxRoot<OCamlList<String>> = strings.as_slice().to_boxroot(cr);
+    assert_eq!(ocaml_list.to_rust::<Vec<String>>(cr), strings);
+
+    let strs = ["x", "y"];
+    let ocaml_list: BoxRoot<OCamlList<String>> = strs.to_boxroot(cr);
+    assert_eq!(ocaml_list.to_rust::<Vec<String>>(cr), strs);
+    let ocaml_list: BoxRoot<OCamlList<String>> = (&strs[..]).to_boxroot(cr);
+    assert_eq!(ocaml_list.to_rust::<Vec<String>>(cr), strs);
+}
+
+#[cfg(test)]
+static DROPPED_STATES: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+
+#[cfg(test)]
+struct CustomState {
+    counter: i64,
+}
+
+#[cfg(test)]
+impl Drop for CustomState {
+    fn drop(&mut self) {
+        DROPPED_STATES.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+    }
+}
+
+#[test]
+#[serial]
+fn test_custom_box() {
+    OCamlRuntime::init_persistent();
+    let cr = unsafe { OCamlRuntime::recover_handle() };
+
+    let state: BoxRoot<Custom<CustomState>> =
+        alloc_custom_box(cr, Box::new(CustomState { counter: 42 })).root();
+    ocaml::gc_compact(cr, &OCaml::unit());
+    assert_eq!(cr.get(&state).custom_ref().counter, 42);
+    assert_eq!(DROPPED_STATES.load(std::sync::atomic::Ordering::SeqCst), 0);
+
+    drop(state);
+    ocaml::gc_compact(cr, &OCaml::unit());
+    assert_eq!(DROPPED_STATES.load(std::sync::atomic::Ordering::SeqCst), 1);
+}
+
+#[test]
+#[serial]
+fn test_checked_float_conversion() {
+    OCamlRuntime::init_persistent();
+    let cr = unsafe { OCamlRuntime::recover_handle() };
+
+    let x: BoxRoot<OCamlFloat> = 1.5f64.try_to_boxroot(cr).unwrap();
+    assert_eq!(x.to_rust::<f64>(cr), 1.5);
+    let x: Result<BoxRoot<OCamlFloat>, _> = f64::NAN.try_to_boxroot(cr);
+    assert!(x.is_err());
+    let x: Result<BoxRoot<OCamlFloat>, _> = f64::NEG_INFINITY.try_to_boxroot(cr);
+    assert!(x.is_err());
+}
+
+#[test]
+#[serial]
+fn test_fold_into_ocaml() {
+    OCamlRuntime::init_persistent();
+    let cr = unsafe { OCamlRuntime::recover_handle() };
+
+    let add: BoxRoot<fn(OCamlInt, OCamlInt) -> OCamlInt> = ocaml::int_adder(cr, &OCaml::unit());
+    let zero: BoxRoot<OCamlInt> = 0i64.to_boxroot(cr);
+    let sum: i64 = fold_into_ocaml(cr, 1..=1000i64, &zero, &add)
+        .unwrap()
+        .to_rust();
+    assert_eq!(sum, 500500);
+}
+
+#[test]
+#[serial]
+fn test_phantom_data_to_ocaml() {
+    OCamlRuntime::init_persistent();
+    let cr = unsafe { OCamlRuntime::recover_handle() };
+
+    let tagged = ocaml::Tagged::<String> {
+        id: 7,
+        marker: std::marker::PhantomData,
+    };
+    let tuple: BoxRoot<(OCamlInt, ())> = tagged.to_boxroot(cr);
+    assert_eq!(tuple.to_rust::<(i64, ())>(cr), (7, ()));
+}
+
+#[test]
+#[serial]
+fn test_bigarray_from_vec() {
+    OCamlRuntime::init_persistent();
+    let cr = unsafe { OCamlRuntime::recover_handle() };
+
+    let data: Vec<f64> = (0..1_000_000).map(|i| i as f64).collect();
+    let data_ptr = data.as_ptr();
+    let arr_ocaml: BoxRoot<bigarray::Array1<f64>> = alloc_bigarray1_from_vec(cr, data).root();
+    assert_eq!(cr.get(&arr_ocaml).as_slice().as_ptr(), data_ptr);
+    assert_eq!(cr.get(&arr_ocaml).len(), 1_000_000);
+    assert_eq!(cr.get(&arr_ocaml).as_slice()[123_456], 123_456.0);
+    let sum: f64 = ocaml::sum_float_array(cr, &arr_ocaml).to_rust(cr);
+    assert_eq!(sum, 499_999_500_000.0);
+
+    drop(arr_ocaml);
+    ocaml::gc_compact(cr, &OCaml::unit());
+}
+
+#[test]
+#[serial]
+fn test_bytes_bigarray_from_vec() {
+    OCamlRuntime::init_persistent();
+    let cr = unsafe { OCamlRuntime::recover_handle() };
+
+    let len = 64 * 1024 * 1024;
+    let data: Vec<u8> = (0..len).map(|i| (i % 251) as u8).collect();
+    let data_ptr = data.as_ptr();
+    let arr_ocaml: BoxRoot<bigarray::Array1<u8>> = alloc_bigarray1_from_vec(cr, data).root();
+    assert_eq!(cr.get(&arr_ocaml).as_slice().as_ptr(), data_ptr);
+
+    let ocaml_len: i64 = ocaml::bytes_array_length(cr, &arr_ocaml).to_rust(cr);
+    assert_eq!(ocaml_len, len as i64);
+    for &i in &[0i64, 250, 251, 1_000_003, len as i64 - 1] {
+        let index = i.to_boxroot(cr);
+        let byte: i64 = ocaml::bytes_array_get(cr, &arr_ocaml, &index).to_rust(cr);
+        assert_eq!(byte, i % 251);
+    }
+
+    drop(arr_ocaml);
+    ocaml::gc_compact(cr, &OCaml::unit());
+}
+
+#[cfg(test)]
+fn to_ocaml_string<T: ToOCaml<String>>(cr: &mut OCamlRuntime, value: T) -> String {
+    value.to_boxroot(cr).to_rust(cr)
+}
+
+#[test]
+#[serial]
+fn test_owned_box_to_ocaml() {
+    OCamlRuntime::init_persistent();
+    let cr = unsafe { OCamlRuntime::recover_handle() };
+
+    let boxed = Box::new("boxed".to_owned());
+    assert_eq!(to_ocaml_string(cr, boxed), "boxed");
+
+    let boxes: Vec<Box<String>> = vec![Box::new("a".to_owned()), Box::new("b".to_owned())];
+    let ocaml_list: BoxRoot<OCamlList<String>> = boxes.to_boxroot(cr);
+    assert_eq!(ocaml_list.to_rust::<Vec<String>>(cr), vec!["a", "b"]);
+}
+
+#[test]
+#[serial]
+fn test_list_to_queues() {
+    OCamlRuntime::init_persistent();
+    let cr = unsafe { OCamlRuntime::recover_handle() };
+
+    let ocaml_list: BoxRoot<OCamlList<OCamlInt>> = vec![1i64, 2, 3].to_boxroot(cr);
+
+    let mut queue: VecDeque<i64> = ocaml_list.to_rust(cr);
+    assert_eq!(queue.front(), Some(&1));
+    assert_eq!(queue.back(), Some(&3));
+    assert_eq!(queue.pop_front(), Some(1));
+
+    let linked: LinkedList<i64> = ocaml_list.to_rust(cr);
+    assert_eq!(linked.front(), Some(&1));
+    assert_eq!(linked.back(), Some(&3));
+    assert_eq!(linked.into_iter().collect::<Vec<_>>(), vec![1, 2, 3]);
+}
+
+#[test]
+#[serial]
+fn test_int128_bytes_roundtrip() {
+    OCamlRuntime::init_persistent();
+    let cr = unsafe { OCamlRuntime::recover_handle() };
+
+    let bytes: BoxRoot<OCamlBytes> = u128::MAX.to_boxroot(cr);
+    assert_eq!(bytes.to_rust::<Vec<u8>>(cr), vec![0xFF; 16]);
+    assert_eq!(bytes.to_rust::<u128>(cr), u128::MAX);
+
+    let n: i128 = -(1i128 << 100) - 42;
+    let bytes: BoxRoot<OCamlBytes> = n.to_boxroot(cr);
+    assert_eq!(bytes.to_rust::<Vec<u8>>(cr), n.to_be_bytes().to_vec());
+    assert_eq!(bytes.to_rust::<i128>(cr), n);
+}
+
+#[test]
+#[serial]
+fn test_dispatch_panicking_call() {
+    OCamlRuntime::init_persistent();
+    let cr = unsafe { OCamlRuntime::recover_handle() };
+
+    let dispatcher = OCamlDispatcher::new();
+    let handle = dispatcher.handle();
+    let failed = handle.dispatch(|_cr| -> i64 { panic!("dispatched call failed") });
+    let succeeded = handle.dispatch(|cr| {
+        let num: BoxRoot<OCamlInt> = 4i64.to_boxroot(cr);
+        ocaml::twice(cr, &num).to_rust::<i64>(cr)
+    });
+
+    assert_eq!(dispatcher.run_pending(cr), 2);
+    assert!(failed.recv().is_err());
+    assert_eq!(succeeded.recv().unwrap(), 8);
+}
+
+#[test]
+#[serial]
+fn test_dispatch_from_worker_threads() {
+    OCamlRuntime::init_persistent();
+    let cr = unsafe { OCamlRuntime::recover_handle() };
+
+    let dispatcher = OCamlDispatcher::new();
+    let workers: Vec<_> = (0..2i64)
+        .map(|worker| {
+            let handle = dispatcher.handle();
+            std::thread::spawn(move || {
+                let results: Vec<_> = (0..10i64)
+                    .map(|i| {
+                        let n = worker * 100 + i;
+                        handle.dispatch(move |cr| {
+                            let num: BoxRoot<OCamlInt> = n.to_boxroot(cr);
+                            ocaml::twice(cr, &num).to_rust::<i64>(cr)
+                        })
+                    })
+                    .collect();
+                results
+                    .into_iter()
+                    .map(|result| result.recv().unwrap())
+                    .collect::<Vec<i64>>()
+            })
+        })
+        .collect();
+
+    for _ in 0..20 {
+        dispatcher.run_one(cr);
+    }
+    assert_eq!(dispatcher.run_pending(cr), 0);
+
+    let results: Vec<Vec<i64>> = workers.into_iter().map(|w| w.join().unwrap()).collect();
+    assert_eq!(results[0], (0..10).map(|i| i * 2).collect::<Vec<_>>());
+    assert_eq!(
+        results[1],
+        (0..10).map(|i| (100 + i) * 2).collect::<Vec<_>>()
+    );
+}
+
+#[cfg(test)]
+struct Version(u32, u32);
+
+#[cfg(test)]
+impl std::fmt::Display for Version {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "v{}.{}", self.0, self.1)
+    }
+}
+
+#[test]
+#[serial]
+fn test_display_to_ocaml() {
+    OCamlRuntime::init_persistent();
+    let cr = unsafe { OCamlRuntime::recover_handle() };
+
+    let s: BoxRoot<String> = display_to_ocaml(cr, &-42i32).root();
+    assert_eq!(s.to_rust::<String>(cr), "-42");
+    let s: BoxRoot<String> = display_to_ocaml(cr, &Version(1, 2)).root();
+    assert_eq!(s.to_rust::<String>(cr), "v1.2");
+    let value: &dyn std::fmt::Display = &Version(3, 4);
+    let s: BoxRoot<String> = display_to_ocaml(cr, value).root();
+    assert_eq!(s.to_rust::<String>(cr), "v3.4");
+}
+
+#[test]
+#[serial]
+fn test_derive_transparent_newtype() {
+    OCamlRuntime::init_persistent();
+    let cr = unsafe { OCamlRuntime::recover_handle() };
+
+    let id = ocaml::UserId(21);
+    let n: BoxRoot<OCamlInt> = id.to_boxroot(cr);
+    assert_eq!(n.to_rust::<i64>(cr), 21);
+    let doubled: i64 = ocaml::twice(cr, &n).to_rust(cr);
+    assert_eq!(doubled, 42);
+}
+
+#[test]
+#[serial]
+fn test_list_conversion_with_gc_safepoints() {
+    OCamlRuntime::init_persistent();
+    let cr = unsafe { OCamlRuntime::recover_handle() };
+
+    let strings: Vec<String> = (0..10_000).map(|i| i.to_string()).collect();
+    let default: BoxRoot<OCamlList<String>> = ocaml_list_from_iter(cr, &strings).root();
+    let options = ConversionOptions::new().minor_gc_every(100);
+    let with_gc: BoxRoot<OCamlList<String>> =
+        ocaml_list_from_iter_with_options(cr, &strings, options).root();
+    let forward: BoxRoot<OCamlList<String>> =
+        ocaml_list_from_forward_iter_with_options(cr, strings.iter(), options).root();
+    assert_eq!(
+        with_gc.to_rust::<Vec<String>>(cr),
+        default.to_rust::<Vec<String>>(cr)
+    );
+    assert_eq!(forward.to_rust::<Vec<String>>(cr), strings);
+}
+
+#[test]
+#[serial]
+fn test_control_flow_to_ocaml() {
+    OCamlRuntime::init_persistent();
+    let cr = unsafe { OCamlRuntime::recover_handle() };
+
+    let flow: ControlFlow<String, i64> = ControlFlow::Continue(1);
+    let ocaml_flow: BoxRoot<OCamlControlFlow<String, OCamlInt>> = flow.to_boxroot(cr);
+    assert_eq!(cr.get(&ocaml_flow).tag_value(), 0);
+    assert_eq!(
+        ocaml_flow.to_rust::<ControlFlow<String, i64>>(cr),
+        ControlFlow::Continue(1)
+    );
+
+    let flow: ControlFlow<String, i64> = ControlFlow::Break("stop".to_owned());
+    let ocaml_flow: BoxRoot<OCamlControlFlow<String, OCamlInt>> = flow.to_boxroot(cr);
+    assert_eq!(cr.get(&ocaml_flow).tag_value(), 1);
+    assert_eq!(
+        ocaml_flow.to_rust::<ControlFlow<String, i64>>(cr),
+        ControlFlow::Break("stop".to_owned())
+    );
+}
+
+#[test]
+#[serial]
+fn test_list_len_and_nth() {
+    OCamlRuntime::init_persistent();
+    let cr = unsafe { OCamlRuntime::recover_handle() };
+
+    let ocaml_list: BoxRoot<OCamlList<OCamlInt>> = vec![10i64, 20, 30, 40, 50].to_boxroot(cr);
+    let list = cr.get(&ocaml_list);
+    assert_eq!(list.len(), 5);
+    assert!(!list.is_empty());
+    assert_eq!(list.nth(0).map(|n| n.to_rust::<i64>()), Some(10));
+    assert_eq!(list.nth(3).map(|n| n.to_rust::<i64>()), Some(40));
+    assert_eq!(list.nth(4).map(|n| n.to_rust::<i64>()), Some(50));
+    assert!(list.nth(5).is_none());
+
+    let empty: OCaml<OCamlList<OCamlInt>> = OCaml::nil();
+    assert_eq!(empty.len(), 0);
+    assert!(empty.nth(0).is_none());
+}
+
+#[test]
+#[serial]
+fn test_bool_to_ocaml_int() {
+    OCamlRuntime::init_persistent();
+    let cr = unsafe { OCamlRuntime::recover_handle() };
+
+    let flag: BoxRoot<OCamlInt> = OCaml::of_bool_as_int(true).root();
+    assert_eq!(flag.to_rust::<i64>(cr), 1);
+    let flag: BoxRoot<OCamlInt> = OCaml::of_bool_as_int(false).root();
+    assert_eq!(flag.to_rust::<i64>(cr), 0);
+    // Inference picks the OCaml `bool` representation
+    let flag = true.to_boxroot(cr);
+    assert!(flag.to_rust::<bool>(cr));
+}
+
+#[test]
+#[serial]
+fn test_closure_try_call() {
+    OCamlRuntime::init_persistent();
+    let cr = unsafe { OCamlRuntime::recover_handle() };
+
+    let add: BoxRoot<fn(OCamlInt, OCamlInt) -> OCamlInt> = ocaml::int_adder(cr, &OCaml::unit());
+    let sum: i64 = add.try_call(cr, &40i64, &2i64).unwrap().to_rust();
+    assert_eq!(sum, 42);
+    let one: BoxRoot<OCamlInt> = 1i64.to_boxroot(cr);
+    let sum: i64 = add.try_call(cr, &one, &2i64).unwrap().to_rust();
+    assert_eq!(sum, 3);
+
+    let mul_add: BoxRoot<fn(OCamlInt, OCamlInt, OCamlInt) -> OCamlInt> =
+        ocaml::mul_add(cr, &OCaml::unit());
+    let result: i64 = mul_add.try_call(cr, &6i64, &7i64, &1i64).unwrap().to_rust();
+    assert_eq!(result, 43);
+
+    let failing: BoxRoot<fn(String) -> OCamlInt> = ocaml::failing_closure(cr, &OCaml::unit());
+    match failing.try_call(cr, &"closure failed".to_owned()) {
+        Ok(_) => panic!("the closure should have raised an exception"),
+        Err(error) => assert_eq!(error.message(), Some("closure failed".to_owned())),
+    }
+}
+
+#[test]
+#[serial]
+fn test_pair_slice_to_assoc_list() {
+    OCamlRuntime::init_persistent();
+    let cr = unsafe { OCamlRuntime::recover_handle() };
+
+    let pairs: &[(i64, &str)] = &[(1, "a"), (2, "b")];
+    let assoc: BoxRoot<OCamlList<(OCamlInt, String)>> = pairs.to_boxroot(cr);
+    let first = cr.get(&assoc).nth(0).unwrap();
+    assert_eq!(first.fst().to_rust::<i64>(), 1);
+    assert_eq!(first.snd().to_rust::<String>(), "a");
+    assert_eq!(
+        assoc.to_rust::<Vec<(i64, String)>>(cr),
+        vec![(1, "a".to_owned()), (2, "b".to_owned())]
+    );
+}
+
+#[test]
+#[serial]
+fn test_ocaml_int_bounds() {
+    let bits = std::mem::size_of::<isize>() as u32 * 8 - 1;
+    assert_eq!(ocaml_interop::OCAML_INT_BITS, bits);
+    assert_eq!(OCamlRuntime::max_int(), (isize::MAX >> 1) as i64);
+    assert_eq!(OCamlRuntime::min_int(), (isize::MIN >> 1) as i64);
+
+    OCamlRuntime::init_persistent();
+    let cr = unsafe { OCamlRuntime::recover_handle() };
+    let n: BoxRoot<OCamlInt> = OCamlRuntime::max_int().try_to_boxroot(cr).unwrap();
+    assert_eq!(n.to_rust::<i64>(cr), OCamlRuntime::max_int());
+    let n: Result<BoxRoot<OCamlInt>, _> = (OCamlRuntime::max_int() + 1).try_to_boxroot(cr);
+    assert!(n.is_err());
+}
+
+#[test]
+#[serial]
+fn test_weak_to_ocaml_option() {
+    OCamlRuntime::init_persistent();
+    let cr = unsafe { OCamlRuntime::recover_handle() };
+
+    let strong = Rc::new("cached".to_owned());
+    let weak = Rc::downgrade(&strong);
+    let value: BoxRoot<Option<String>> = weak.to_boxroot(cr);
+    assert_eq!(
+        value.to_rust::<Option<String>>(cr),
+        Some("cached".to_owned())
+    );
+    drop(strong);
+    let value: BoxRoot<Option<String>> = weak.to_boxroot(cr);
+    assert_eq!(value.to_rust::<Option<String>>(cr), None);
+
+    let strong = Arc::new(7i64);
+    let weak = Arc::downgrade(&strong);
+    let value: BoxRoot<Option<OCamlInt>> = weak.to_boxroot(cr);
+    assert_eq!(value.to_rust::<Option<i64>>(cr), Some(7));
+    drop(strong);
+    let value: BoxRoot<Option<OCamlInt>> = weak.to_boxroot(cr);
+    assert_eq!(value.to_rust::<Option<i64>>(cr), None);
+}
+
+#[test]
+#[serial]
+fn test_bigarray_sub() {
+    OCamlRuntime::init_persistent();
+    let cr = unsafe { OCamlRuntime::recover_handle() };
+
+    let data: Vec<f64> = (0..10).map(|i| i as f64).collect();
+    let arr: BoxRoot<bigarray::Array1<f64>> = data.as_slice().to_boxroot(cr);
+    let view: BoxRoot<bigarray::Array1<f64>> = arr.sub(cr, 2, 3).root();
+    ocaml::gc_compact(cr, &OCaml::unit());
+    assert_eq!(cr.get(&view).as_slice(), &[2.0, 3.0, 4.0]);
+    assert_eq!(
+        cr.get(&view).as_slice().as_ptr(),
+        cr.get(&arr).as_slice()[2..].as_ptr()
+    );
+
+    drop(arr);
+    ocaml::gc_compact(cr, &OCaml::unit());
+    let sum: f64 = ocaml::sum_float_array(cr, &view).to_rust(cr);
+    assert_eq!(sum, 9.0);
+}
+
+#[test]
+#[serial]
+fn test_flatten_nested_result() {
+    OCamlRuntime::init_persistent();
+    let cr = unsafe { OCamlRuntime::recover_handle() };
+
+    let nested: Result<Result<i64, String>, String> = Ok(Ok(1));
+    let result: BoxRoot<Result<OCamlInt, String>> = flatten_to_ocaml(cr, &nested).root();
+    assert_eq!(result.to_rust::<Result<i64, String>>(cr), Ok(1));
+
+    let nested: Result<Result<i64, String>, String> = Ok(Err("inner".to_owned()));
+    let result: BoxRoot<Result<OCamlInt, String>> = flatten_to_ocaml(cr, &nested).root();
+    assert_eq!(
+        result.to_rust::<Result<i64, String>>(cr),
+        Err("inner".to_owned())
+    );
+
+    let nested: Result<Result<i64, String>, String> = Err("outer".to_owned());
+    let result: BoxRoot<Result<OCamlInt, String>> = flatten_to_ocaml(cr, &nested).root();
+    assert_eq!(
+        result.to_rust::<Result<i64, String>>(cr),
+        Err("outer".to_owned())
+    );
+}
+
+#[test]
+#[serial]
+fn test_instant_to_ocaml() {
+    OCamlRuntime::init_persistent();
+    let cr = unsafe { OCamlRuntime::recover_handle() };
+
+    let base = std::time::Instant::now();
+    let later = base + Duration::from_millis(1500);
+    let elapsed: BoxRoot<OCamlFloat> = instant_to_ocaml(cr, later, base).root();
+    assert_eq!(elapsed.to_rust::<f64>(cr), 1.5);
+    let elapsed: BoxRoot<OCamlFloat> = instant_to_ocaml(cr, base, later).root();
+    assert_eq!(elapsed.to_rust::<f64>(cr), 0.0);
+}
+
+#[test]
+#[serial]
+fn test_cell_snapshots_to_ocaml() {
+    OCamlRuntime::init_persistent();
+    let cr = unsafe { OCamlRuntime::recover_handle() };
+
+    let config = std::cell::RefCell::new("verbose".to_owned());
+    let snapshot: BoxRoot<String> = (&config).to_boxroot(cr);
+    config.borrow_mut().push_str(" mode");
+    assert_eq!(snapshot.to_rust::<String>(cr), "verbose");
+    let snapshot: BoxRoot<String> = config.to_boxroot(cr);
+    assert_eq!(snapshot.to_rust::<String>(cr), "verbose mode");
+
+    let counter = std::cell::Cell::new(41i64);
+    let snapshot: BoxRoot<OCamlInt> = counter.to_boxroot(cr);
+    counter.set(42);
+    assert_eq!(snapshot.to_rust::<i64>(cr), 41);
+    let snapshot: BoxRoot<OCamlInt> = (&counter).to_boxroot(cr);
+    assert_eq!(snapshot.to_rust::<i64>(cr), 42);
+}
+
+#[test]
+#[serial]
+fn test_ocaml_string_builder() {
+    OCamlRuntime::init_persistent();
+    let cr = unsafe { OCamlRuntime::recover_handle() };
+
+    let mut builder = OCamlStringBuilder::new(cr, 6);
+    builder.write_str("foo");
+    assert_eq!(builder.remaining(), 3);
+    builder.write_bytes(b"bar");
+    let s: BoxRoot<String> = builder.build().root();
+    assert_eq!(s.to_rust::<String>(cr), "foobar");
+}
+
+#[cfg(test)]
+struct PanicsOnConversion;
+
+#[cfg(test)]
+unsafe impl FromOCaml<OCamlInt> for PanicsOnConversion {
+    fn from_ocaml(_v: OCaml<OCamlInt>) -> Self {
+        panic!("the payload of None must not be converted")
+    }
+}
+
+#[test]
+#[serial]
+fn test_none_from_ocaml_skips_payload() {
+    OCamlRuntime::init_persistent();
+    let cr = unsafe { OCamlRuntime::recover_handle() };
+
+    let none: OCaml<Option<OCamlInt>> = OCaml::none();
+    assert!(none.to_rust::<Option<PanicsOnConversion>>().is_none());
+
+    let none: BoxRoot<Option<OCamlInt>> = None::<i64>.to_boxroot(cr);
+    assert!(none.to_rust::<Option<PanicsOnConversion>>(cr).is_none());
+}
+
+#[test]
+#[serial]
+fn test_bitflags_to_ocaml() {
+    OCamlRuntime::init_persistent();
+    let cr = unsafe { OCamlRuntime::recover_handle() };
+
+    struct Permissions(u32);
+
+    impl Permissions {
+        const READ: Self = Permissions(0b001);
+        const EXECUTE: Self = Permissions(0b100);
+
+        fn bits(&self) -> u32 {
+            self.0
+        }
+    }
+
+    ocaml_interop::impl_ocaml_bitflags!(Permissions);
+
+    let perms = Permissions(Permissions::READ.bits() | Permissions::EXECUTE.bits());
+    let ocaml_perms: BoxRoot<OCamlInt> = perms.to_boxroot(cr);
+    assert_eq!(ocaml_perms.to_rust::<i64>(cr), 0b101);
+}
+
+#[test]
+#[serial]
+#[should_panic(expected = "the bits of WideFlags don't fit in an OCaml int")]
+fn test_bitflags_with_high_bit_set_panic() {
+    OCamlRuntime::init_persistent();
+    let cr = unsafe { OCamlRuntime::recover_handle() };
+
+    struct WideFlags(u64);
+
+    impl WideFlags {
+        fn bits(&self) -> u64 {
+            self.0
+        }
+    }
+
+    ocaml_interop::impl_ocaml_bitflags!(WideFlags);
+
+    let _: BoxRoot<OCamlInt> = WideFlags(u64::MAX).to_boxroot(cr);
+}
+
+#[test]
+#[serial]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "use of a stale OCaml value")]
+fn test_stale_value_access_panics() {
+    OCamlRuntime::init_persistent();
+    let cr = unsafe { OCamlRuntime::recover_handle() };
+    // A second handle lets the first one be used while `stale` still borrows it
+    let other_cr = unsafe { OCamlRuntime::recover_handle() };
+
+    let stale: OCaml<String> = "stale".to_owned().to_ocaml(cr);
+    let _other: BoxRoot<String> = "other".to_owned().to_boxroot(other_cr);
+    let _ = stale.to_rust::<String>();
+}
+
+#[test]
+#[serial]
+fn test_bool_slice_to_ocaml_list() {
+    OCamlRuntime::init_persistent();
+    let cr = unsafe { OCamlRuntime::recover_handle() };
+
+    let bits: &[bool] = &[true, false, true];
+    let ocaml_bits: BoxRoot<OCamlList<bool>> = bits.to_boxroot(cr);
+    assert_eq!(ocaml_bits.to_rust::<Vec<bool>>(cr), vec![true, false, true]);
+
+    let ocaml_bits: BoxRoot<OCamlList<bool>> = vec![false, true].to_boxroot(cr);
+    assert_eq!(ocaml_bits.to_rust::<Vec<bool>>(cr), vec![false, true]);
+}
+
+#[test]
+#[serial]
+fn test_tuples_with_unit_fields() {
+    OCamlRuntime::init_persistent();
+    let cr = unsafe { OCamlRuntime::recover_handle() };
+
+    let pair: BoxRoot<((), OCamlInt)> = ((), 5i64).to_boxroot(cr);
+    assert_eq!(unsafe { cr.get(&pair).fst().raw() }, unsafe {
+        OCaml::unit().raw()
+    });
+    assert_eq!(pair.to_rust::<((), i64)>(cr), ((), 5));
+
+    let pair: BoxRoot<(OCamlInt, ())> = (-7i64, ()).to_boxroot(cr);
+    assert_eq!(unsafe { cr.get(&pair).snd().raw() }, unsafe {
+        OCaml::unit().raw()
+    });
+    assert_eq!(pair.to_rust::<(i64, ())>(cr), (-7, ()));
+}
+
+#[test]
+#[serial]
+fn test_deep_copy_is_independent() {
+    OCamlRuntime::init_persistent();
+    let cr = unsafe { OCamlRuntime::recover_handle() };
+
+    let n = 3i64.to_boxroot(cr);
+    let original = ocaml::make_counters(cr, &n);
+    let copy = deep_copy(cr, &original);
+    // A second root of the same value is not a copy
+    let same: BoxRoot<OCamlList<(ocaml::IntRef, String)>> = BoxRoot::new(cr.get(&original));
+
+    ocaml::gc_minor(cr, &OCaml::unit());
+    ocaml::incr_counters(cr, &original);
+
+    let values: Vec<i64> = ocaml::counter_values(cr, &original).to_rust(cr);
+    assert_eq!(values, vec![1, 2, 3]);
+    let values: Vec<i64> = ocaml::counter_values(cr, &same).to_rust(cr);
+    assert_eq!(values, vec![1, 2, 3]);
+    let values: Vec<i64> = ocaml::counter_values(cr, &copy).to_rust(cr);
+    assert_eq!(values, vec![0, 1, 2]);
+
+    let names: Vec<(i64, String)> = unsafe {
+        cr.get(&copy)
+            .iter()
+            .map(|pair| {
+                (
+                    pair.fst().field::<OCamlInt>(0).to_rust(),
+                    pair.snd().to_rust(),
+                )
+            })
+            .collect()
+    };
+    assert_eq!(names[2], (2, "2".to_owned()));
+}
+
+#[test]
+#[serial]
+fn test_deep_copy_of_tag_245_blocks() {
+    OCamlRuntime::init_persistent();
+    let cr = unsafe { OCamlRuntime::recover_handle() };
+
+    // An ordinary block before OCaml 5, and the tag of continuations since then
+    struct Tag245Block;
+
+    let n = 2i64.to_boxroot(cr);
+    let counters = ocaml::make_counters(cr, &n);
+    let block: BoxRoot<Tag245Block> = unsafe {
+        let raw = ocaml_interop::internal::caml_alloc(1, 245);
+        ocaml_interop::internal::store_field(raw, 0, counters.get_raw());
+        BoxRoot::new(OCaml::new(cr, raw))
+    };
+    let copy = deep_copy(cr, &block);
+
+    ocaml::incr_counters(cr, &counters);
+
+    let copied_counters: BoxRoot<OCamlList<(ocaml::IntRef, String)>> =
+        unsafe { cr.get(&copy).field(0) }.root();
+    let values: Vec<i64> = ocaml::counter_values(cr, &copied_counters).to_rust(cr);
+    let major_version: i64 = ocaml::ocaml_major_version(cr, &OCaml::unit()).to_rust(cr);
+    if major_version >= 5 {
+        // Continuations are shared with the original
+        assert_eq!(values, vec![1, 2]);
+    } else {
+        assert_eq!(values, vec![0, 1]);
+    }
+}
+
+#[test]
+#[serial]
+fn test_cow_slice_to_ocaml_list() {
+    OCamlRuntime::init_persistent();
+    let cr = unsafe { OCamlRuntime::recover_handle() };
+
+    let values = [3i64, 1, 2];
+    let borrowed: Cow<[i64]> = Cow::Borrowed(&values);
+    let owned: Cow<[i64]> = Cow::Owned(values.to_vec());
+
+    let borrowed_list: BoxRoot<OCamlList<OCamlInt>> = borrowed.to_boxroot(cr);
+    let owned_list: BoxRoot<OCamlList<OCamlInt>> = owned.to_boxroot(cr);
+    assert_eq!(borrowed_list.to_rust::<Vec<i64>>(cr), vec![3, 1, 2]);
+    assert_eq!(
+        owned_list.to_rust::<Vec<i64>>(cr),
+        borrowed_list.to_rust::<Vec<i64>>(cr)
+    );
+}
+
+#[test]
+#[serial]
+fn test_option_slice_to_ocaml_list() {
+    OCamlRuntime::init_persistent();
+    let cr = unsafe { OCamlRuntime::recover_handle() };
+
+    let column: &[Option<i64>] = &[Some(1i64), None, Some(3)];
+    let ocaml_column: BoxRoot<OCamlList<Option<OCamlInt>>> = column.to_boxroot(cr);
+    assert_eq!(
+        ocaml_column.to_rust::<Vec<Option<i64>>>(cr),
+        vec![Some(1), None, Some(3)]
+    );
+
+    let column: Vec<Option<String>> = vec![None, Some("two".to_owned())];
+    let ocaml_column: BoxRoot<OCamlList<Option<String>>> = column.to_boxroot(cr);
+    assert_eq!(ocaml_column.to_rust::<Vec<Option<String>>>(cr), column);
+}
+
+#[test]
+#[serial]
+fn test_gc_stats_count_list_allocation() {
+    OCamlRuntime::init_persistent();
+    let cr = unsafe { OCamlRuntime::recover_handle() };
+
+    let before = cr.gc_stats();
+    let after_noop = cr.gc_stats();
+    let noop_words = after_noop.minor_words - before.minor_words;
+
+    let numbers: Vec<i64> = (0..10_000).collect();
+    let _list: BoxRoot<OCamlList<OCamlInt>> = numbers.to_boxroot(cr);
+    let after_list = cr.gc_stats();
+    let list_words = after_list.minor_words - after_noop.minor_words;
+
+    // Each cons cell is a header and two fields
+    assert!(list_words >= 30_000.0);
+    assert!(list_words > noop_words);
+}
+
+#[test]
+#[serial]
+fn test_blit_str_into_bytes() {
+    OCamlRuntime::init_persistent();
+    let cr = unsafe { OCamlRuntime::recover_handle() };
+
+    let buffer: BoxRoot<OCamlBytes> = "hello world".to_boxroot(cr);
+    let mut bytes = buffer.get(cr);
+    blit_str_into(cr, &mut bytes, 6, "OCaml");
+    assert_eq!(buffer.to_rust::<String>(cr), "hello OCaml");
+
+    let mut bytes = buffer.get(cr);
+    blit_str_into(cr, &mut bytes, 0, "HELLO");
+    assert_eq!(buffer.to_rust::<Vec<u8>>(cr), b"HELLO OCaml");
+}
+
+#[test]
+#[serial]
+fn test_iter_to_tuple() {
+    OCamlRuntime::init_persistent();
+    let cr = unsafe { OCamlRuntime::recover_handle() };
+
+    let names = ["x", "y", "z"];
+    let row: BoxRoot<(String, String, String)> =
+        iter_to_tuple3(cr, names.iter().map(|name| name.to_string())).root();
+    assert_eq!(
+        row.to_rust::<(String, String, String)>(cr),
+        ("x".to_owned(), "y".to_owned(), "z".to_owned())
+    );
+}
+
+#[test]
+#[serial]
+#[should_panic(expected = "expected an iterator of 3 items to build a tuple, got 2 items")]
+fn test_iter_to_tuple_too_short() {
+    OCamlRuntime::init_persistent();
+    let cr = unsafe { OCamlRuntime::recover_handle() };
+
+    let _row: OCaml<(OCamlInt, OCamlInt, OCamlInt)> = iter_to_tuple3(cr, vec![1i64, 2]);
+}
+
+#[test]
+#[serial]
+fn test_num_wrappers_to_ocaml() {
+    OCamlRuntime::init_persistent();
+    let cr = unsafe { OCamlRuntime::recover_handle() };
+
+    let n: BoxRoot<OCamlInt64> = Wrapping(5i64).to_boxroot(cr);
+    assert_eq!(n.to_rust::<i64>(cr), 5);
+    let n: BoxRoot<OCamlInt> = (Wrapping(7i64) * Wrapping(-3)).to_boxroot(cr);
+    assert_eq!(n.to_rust::<i64>(cr), -21);
+    let n: BoxRoot<OCamlInt32> = (Saturating(i32::MAX) + Saturating(1)).to_boxroot(cr);
+    assert_eq!(n.to_rust::<i32>(cr), i32::MAX);
+}
+
+#[test]
+#[serial]
+fn test_fresh_block_construction() {
+    OCamlRuntime::init_persistent();
+    let cr = unsafe { OCamlRuntime::recover_handle() };
+
+    let tuple = (
+        0i64,
+        "one".to_owned(),
+        2.0f64,
+        Some(3i64),
+        Ok::<i64, String>(4),
+        Err::<i64, String>("five".to_owned()),
+        vec![6i64, 7],
+        true,
+        None::<i64>,
+    );
+    let ocaml_tuple: BoxRoot<(
+        OCamlInt,
+        String,
+        OCamlFloat,
+        Option<OCamlInt>,
+        Result<OCamlInt, String>,
+        Result<OCamlInt, String>,
+        OCamlList<OCamlInt>,
+        bool,
+        Option<OCamlInt>,
+    )> = tuple.to_boxroot(cr);
+
+    // Collect in between, so that the blocks filled directly end up in the major heap
+    ocaml::gc_minor(cr, &OCaml::unit());
+    assert_eq!(
+        ocaml_tuple.to_rust::<(
+            i64,
+            String,
+            f64,
+            Option<i64>,
+            Result<i64, String>,
+            Result<i64, String>,
+            Vec<i64>,
+            bool,
+            Option<i64>
+        )>(cr),
+        tuple
+    );
+}
+
+#[test]
+#[serial]
+fn test_serde_struct_to_ocaml_record() {
+    OCamlRuntime::init_persistent();
+    let cr = unsafe { OCamlRuntime::recover_handle() };
+
+    let item = ocaml::InventoryItem {
+        name: "bolt".to_owned(),
+        count: 12,
+        price: 0.25,
+        tags: vec!["metal".to_owned(), "small".to_owned()],
+        discount: Some(0.1),
+    };
+    let item: BoxRoot<ocaml::InventoryItem> =
+        unsafe { to_ocaml_via_serde(cr, &item) }.unwrap().root();
+    let result: String = ocaml::stringify_inventory_item(cr, &item).to_rust(cr);
+    assert_eq!(result, "bolt x12 @ 0.25 [metal; small] 10% off");
+
+    let item = ocaml::InventoryItem {
+        name: "nut".to_owned(),
+        count: 3,
+        price: 1.0,
+        tags: vec![],
+        discount: None,
+    };
+    let item: BoxRoot<ocaml::InventoryItem> =
+        unsafe { to_ocaml_via_serde(cr, &item) }.unwrap().root();
+    let result: String = ocaml::stringify_inventory_item(cr, &item).to_rust(cr);
+    assert_eq!(result, "nut x3 @ 1.00 [] no discount");
+}
+
+#[test]
+#[serial]
+fn test_serde_float_struct_to_ocaml_record() {
+    OCamlRuntime::init_persistent();
+    let cr = unsafe { OCamlRuntime::recover_handle() };
+
+    let vec = ocaml::Vec2 { vx: -3.0, vy: 0.75 };
+    let vec: BoxRoot<ocaml::Vec2> = unsafe { to_ocaml_via_serde(cr, &vec) }.unwrap().root();
+    let result: String = ocaml::stringify_vec2(cr, &vec).to_rust(cr);
+    assert_eq!(result, "{ vx=-3.00; vy=0.75 }");
+
+    // Tuples are never stored flat
+    let tuple: (f64, f64) =
+        unsafe { to_ocaml_via_serde::<_, (OCamlFloat, OCamlFloat)>(cr, &(1.5, 2.5)) }
+            .unwrap()
+            .to_rust();
+    assert_eq!(tuple, (1.5, 2.5));
+}
+
+#[test]
+#[serial]
+fn test_serde_enum_to_ocaml_polymorphic_variant() {
+    OCamlRuntime::init_persistent();
+    let cr = unsafe { OCamlRuntime::recover_handle() };
+
+    let events = [
+        (ocaml::InventoryEvent::Started, "Started"),
+        (ocaml::InventoryEvent::Moved(-1, 2), "Moved(-1, 2)"),
+        (
+            ocaml::InventoryEvent::Renamed("shelf".to_owned()),
+            "Renamed(shelf)",
+        ),
+        (
+            ocaml::InventoryEvent::Resized {
+                width: 3,
+                height: 4,
+            },
+            "Resized(3x4)",
+        ),
+    ];
+    for (event, expected) in events.iter() {
+        let event: BoxRoot<ocaml::InventoryEvent> =
+            unsafe { to_ocaml_via_serde(cr, event) }.unwrap().root();
+        let result: String = ocaml::stringify_inventory_event(cr, &event).to_rust(cr);
+        assert_eq!(&result, expected);
+    }
+}
+
+#[test]
+#[serial]
+fn test_serde_values_out_of_range() {
+    OCamlRuntime::init_persistent();
+    let cr = unsafe { OCamlRuntime::recover_handle() };
+
+    let result = unsafe { to_ocaml_via_serde::<_, OCamlInt>(cr, &u64::MAX) };
+    assert!(matches!(result, Err(OCamlSerializeError::Fixnum(_))));
+    let result = unsafe { to_ocaml_via_serde::<_, OCamlChar>(cr, &'λ') };
+    assert!(matches!(result, Err(OCamlSerializeError::Char(_))));
+}
@@ -0,0 +1,26 @@
+// Copyright (c) Viable Systems and TezEdge Contributors
+// SPDX-License-Identifier: MIT
+
+// The OCaml runtime is initialized once per process, so this test lives in its own
+// test binary, where nothing else initializes the runtime first.
+
+extern crate rust_caller;
+
+use ocaml_interop::{ocaml, OCaml, OCamlInt, OCamlList, OCamlRuntime, ToOCaml};
+
+ocaml! {
+    fn sys_argv(unit: ()) -> OCamlList<String>;
+    fn twice(num: OCamlInt) -> OCamlInt;
+}
+
+#[test]
+fn test_init_with_args() {
+    let mut cr = OCamlRuntime::init_with_args(&["rust-caller", "--verbose", "input.txt"]);
+
+    let argv: Vec<String> = sys_argv(&mut cr, &OCaml::unit()).to_rust(&cr);
+    assert_eq!(argv, vec!["rust-caller", "--verbose", "input.txt"]);
+
+    let num = 21i64.to_boxroot(&mut cr);
+    let result: i64 = twice(&mut cr, &num).to_rust(&cr);
+    assert_eq!(result, 42);
+}